@@ -0,0 +1,271 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead};
+use std::num::ParseIntError;
+use std::path::Path;
+use std::str::FromStr;
+
+pub mod location_lists;
+pub mod metrics;
+
+pub use location_lists::LocationLists;
+pub use metrics::{
+    jaccard_similarity, max_distance, median_distance, multiset_overlap, squared_distance, Metric,
+};
+
+// Integer type the location IDs are parsed into and scored with.
+pub trait LocationId:
+    Copy + Ord + fmt::Debug + fmt::Display + FromStr<Err = ParseIntError> + TryFrom<i128>
+{
+    // Absolute difference, widened so it can never overflow.
+    fn distance(self, other: Self) -> u128;
+    // Every supported type fits into an i128.
+    fn widen(self) -> i128;
+}
+
+macro_rules! impl_location_id {
+    ($($t:ty),*) => {
+        $(
+            impl LocationId for $t {
+                fn distance(self, other: Self) -> u128 {
+                    self.abs_diff(other) as u128
+                }
+
+                fn widen(self) -> i128 {
+                    self as i128
+                }
+            }
+        )*
+    };
+}
+
+impl_location_id!(u32, i32, i64, u64, i128);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntType {
+    U32,
+    I32,
+    I64,
+    U64,
+    I128,
+}
+
+impl IntType {
+    // Smallest supported type that can hold every value of both columns.
+    pub fn detect(col1: &[i128], col2: &[i128]) -> IntType {
+        let values = || col1.iter().chain(col2.iter());
+        let min = values().min().copied().unwrap_or(0);
+        let max = values().max().copied().unwrap_or(0);
+
+        if min >= i32::MIN as i128 && max <= i32::MAX as i128 {
+            IntType::I32
+        } else if min >= i64::MIN as i128 && max <= i64::MAX as i128 {
+            IntType::I64
+        } else if min >= 0 && max <= u64::MAX as i128 {
+            IntType::U64
+        } else {
+            IntType::I128
+        }
+    }
+}
+
+impl FromStr for IntType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "u32" => Ok(IntType::U32),
+            "i32" => Ok(IntType::I32),
+            "i64" => Ok(IntType::I64),
+            "u64" => Ok(IntType::U64),
+            "i128" => Ok(IntType::I128),
+            _ => Err(format!("Unsupported integer type: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowError {
+    Distance,
+    SquaredDistance,
+    Similarity,
+}
+
+impl fmt::Display for OverflowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OverflowError::Distance => write!(f, "total distance overflowed"),
+            OverflowError::SquaredDistance => write!(f, "squared distance overflowed"),
+            OverflowError::Similarity => write!(f, "similarity score overflowed"),
+        }
+    }
+}
+
+impl std::error::Error for OverflowError {}
+
+pub fn read_and_parse_file<T: LocationId>(file_path: &str) -> io::Result<(Vec<T>, Vec<T>)> {
+    let path = Path::new(file_path);
+    let file = File::open(path)?;
+    let reader = io::BufReader::new(file);
+
+    let mut col1: Vec<T> = Vec::new();
+    let mut col2: Vec<T> = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        let numbers: Vec<&str> = line.split_whitespace().collect();
+        if numbers.len() == 2 {
+            match (parse_id::<T>(numbers[0])?, parse_id::<T>(numbers[1])?) {
+                (Some(num1), Some(num2)) => {
+                    col1.push(num1);
+                    col2.push(num2);
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    Ok((col1, col2))
+}
+
+// Lines that are not numbers are skipped, but numbers that do not fit into the
+// selected type are an error rather than being silently dropped.
+fn parse_id<T: LocationId>(number: &str) -> io::Result<Option<T>> {
+    match number.parse::<T>() {
+        Ok(id) => Ok(Some(id)),
+        Err(_) if is_integer(number) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Location ID out of range: {}", number),
+        )),
+        Err(_) => Ok(None),
+    }
+}
+
+// An optional sign followed by digits, however many, as accepted by `parse`.
+fn is_integer(number: &str) -> bool {
+    let digits = number.strip_prefix(['-', '+']).unwrap_or(number);
+    !digits.is_empty() && digits.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+pub fn narrow_ids<T: LocationId>(places: &[i128]) -> io::Result<Vec<T>> {
+    places
+        .iter()
+        .map(|&place| {
+            T::try_from(place).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Location ID out of range: {}", place),
+                )
+            })
+        })
+        .collect()
+}
+
+pub fn total_distance<T: LocationId>(
+    places_1: &[T],
+    places_2: &[T],
+) -> Result<u128, OverflowError> {
+    places_1
+        .iter()
+        .zip(places_2.iter())
+        .try_fold(0u128, |total, (&place_1, &place_2)| {
+            total
+                .checked_add(place_1.distance(place_2))
+                .ok_or(OverflowError::Distance)
+        })
+}
+
+// The score is summed in an i128 whatever the ID type, as the type detected
+// from the IDs alone is usually far too small for it.
+pub fn get_similarity_score<T: LocationId>(
+    places_1: &[T],
+    places_2: &[T],
+) -> Result<i128, OverflowError> {
+    places_1.iter().try_fold(0i128, |score, &number| {
+        let count = count_number(number, places_2) as i128;
+        number
+            .widen()
+            .checked_mul(count)
+            .and_then(|similarity| score.checked_add(similarity))
+            .ok_or(OverflowError::Similarity)
+    })
+}
+
+pub fn count_number<T: LocationId>(number: T, places: &[T]) -> usize {
+    places.iter().filter(|&place| *place == number).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scores_with_negative_ids() {
+        let mut col1: Vec<i64> = vec![-3, 4, 2, -1, 3, 3];
+        let mut col2: Vec<i64> = vec![4, 3, -5, 3, 9, 3];
+        col1.sort();
+        col2.sort();
+
+        assert_eq!(total_distance(&col1, &col2), Ok(13));
+        assert_eq!(get_similarity_score(&col1, &col2), Ok(22));
+    }
+
+    #[test]
+    fn test_total_distance_does_not_overflow_on_wide_ids() {
+        let col1: Vec<i64> = vec![i64::MIN];
+        let col2: Vec<i64> = vec![i64::MAX];
+
+        assert_eq!(total_distance(&col1, &col2), Ok(u64::MAX as u128));
+    }
+
+    #[test]
+    fn test_similarity_score_is_wider_than_the_ids() {
+        let col1: Vec<i32> = vec![99_999; 300];
+        let col2: Vec<i32> = vec![99_999; 300];
+        assert_eq!(get_similarity_score(&col1, &col2), Ok(99_999 * 300 * 300));
+
+        let col1: Vec<i32> = vec![i32::MAX, i32::MAX];
+        let col2: Vec<i32> = vec![i32::MAX, 1];
+        assert_eq!(get_similarity_score(&col1, &col2), Ok(2 * i32::MAX as i128));
+    }
+
+    #[test]
+    fn test_overflow_is_reported() {
+        let col1: Vec<i128> = vec![i128::MAX, i128::MAX];
+        let col2: Vec<i128> = vec![i128::MAX, 1];
+
+        assert_eq!(
+            get_similarity_score(&col1, &col2),
+            Err(OverflowError::Similarity)
+        );
+
+        let col1: Vec<i128> = vec![i128::MIN, i128::MIN, i128::MIN];
+        let col2: Vec<i128> = vec![i128::MAX, i128::MAX, i128::MAX];
+
+        assert_eq!(total_distance(&col1, &col2), Err(OverflowError::Distance));
+    }
+
+    #[test]
+    fn test_detect_int_type() {
+        assert_eq!(IntType::detect(&[1, 2], &[-3, 4]), IntType::I32);
+        assert_eq!(IntType::detect(&[1 << 40], &[-3]), IntType::I64);
+        assert_eq!(IntType::detect(&[u64::MAX as i128], &[0]), IntType::U64);
+        assert_eq!(IntType::detect(&[u64::MAX as i128], &[-1]), IntType::I128);
+    }
+
+    #[test]
+    fn test_parse_id_rejects_out_of_range() {
+        assert_eq!(parse_id::<u32>("12").unwrap(), Some(12));
+        assert_eq!(parse_id::<u32>("abc").unwrap(), None);
+        assert!(parse_id::<u32>("-1").is_err());
+        assert!(parse_id::<u32>("4294967296").is_err());
+        assert!(parse_id::<i32>("-2147483649").is_err());
+        assert!(parse_id::<i128>("170141183460469231731687303715884105728").is_err());
+        assert!(parse_id::<u64>("-99999999999999999999999999999999999999999").is_err());
+        assert!(parse_id::<i128>("+170141183460469231731687303715884105728").is_err());
+        assert_eq!(parse_id::<u32>("+12").unwrap(), Some(12));
+        assert_eq!(parse_id::<u32>("-").unwrap(), None);
+        assert_eq!(parse_id::<u32>("+").unwrap(), None);
+        assert_eq!(parse_id::<u32>("+-1").unwrap(), None);
+    }
+}
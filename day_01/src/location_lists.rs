@@ -0,0 +1,762 @@
+use std::cell::Cell;
+
+use crate::{LocationId, OverflowError};
+
+// Incrementally maintained pair of location lists.
+//
+// Both columns live in one treap keyed by ID, where every node counts how
+// often its ID occurs in each column. Inserting or removing an ID, looking up
+// its counts and finding the k-th smallest ID of a column are all O(log n).
+// Every subtree also keeps its share of the similarity score, so the score is
+// current after every change and an overflow disappears again once the IDs
+// causing it are removed.
+//
+// The total distance pairs the i-th smallest IDs of both columns, which is the
+// area between the two cumulative counts: over every gap between consecutive
+// IDs, the gap times |#left ≤ x - #right ≤ x|. Every subtree keeps the area and
+// the lowest and highest balance over its gaps, both measured from a balance
+// of 0, so a subtree whose balance keeps one sign is summed in O(1). A query
+// only descends where the balance changes sign, and so costs O(log n) per
+// sign change. That is O(log n) when the columns are mostly shifted against
+// each other, but in the worst case, columns that keep overtaking each other,
+// the balance changes sign at almost every ID and a query is O(n). Updates
+// stay O(log n) either way, and the distance is only measured again on the
+// first query after a change. Areas are kept in an i128, so a distance above
+// i128::MAX is reported as an overflow.
+#[derive(Debug)]
+pub struct LocationLists<T: LocationId = u32> {
+    root: Option<Box<Node<T>>>,
+    seed: u64,
+    // Distance of the current lists, until the next change.
+    distance: Cell<Option<Result<u128, OverflowError>>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Left,
+    Right,
+}
+
+// Which counts a balance follows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Balance {
+    // Left minus right counts.
+    Net,
+    Only(Side),
+}
+
+// `gap` times `balance`, None if it does not fit.
+fn times(gap: u128, balance: i64) -> Option<i128> {
+    i128::try_from(gap).ok()?.checked_mul(balance as i128)
+}
+
+// A balance over the gaps between the consecutive IDs of a subtree, starting
+// at 0 before its smallest ID.
+#[derive(Debug, Clone, Copy, Default)]
+struct Profile {
+    // Balance after the largest ID.
+    total: i64,
+    // Sum of gap times balance, None once it overflowed.
+    area: Option<i128>,
+    // Lowest and highest balance over the gaps, None without any gap.
+    range: Option<(i64, i64)>,
+}
+
+impl Profile {
+    fn point(count: i64) -> Profile {
+        Profile {
+            total: count,
+            area: Some(0),
+            range: None,
+        }
+    }
+
+    // `self` followed by a gap and then `next`, which spans `next_span`.
+    fn join(self, gap: u128, next: Profile, next_span: u128) -> Profile {
+        let shifted = next
+            .range
+            .map(|(low, high)| (low + self.total, high + self.total));
+        let range = [self.range, Some((self.total, self.total)), shifted]
+            .into_iter()
+            .flatten()
+            .reduce(|(low_1, high_1), (low_2, high_2)| (low_1.min(low_2), high_1.max(high_2)));
+        let area = || {
+            self.area?
+                .checked_add(times(gap, self.total)?)?
+                .checked_add(next.area?)?
+                .checked_add(times(next_span, self.total)?)
+        };
+        Profile {
+            total: self.total + next.total,
+            area: area(),
+            range,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Stats<T> {
+    left_size: usize,
+    right_size: usize,
+    min: T,
+    max: T,
+    // Similarity score of the subtree, None once it overflowed.
+    similarity: Option<i128>,
+    net: Profile,
+    lefts: Profile,
+    rights: Profile,
+}
+
+impl<T: LocationId> Stats<T> {
+    fn size(&self, side: Side) -> usize {
+        match side {
+            Side::Left => self.left_size,
+            Side::Right => self.right_size,
+        }
+    }
+
+    fn span(&self) -> u128 {
+        self.min.distance(self.max)
+    }
+
+    fn profile(&self, balance: Balance) -> &Profile {
+        match balance {
+            Balance::Net => &self.net,
+            Balance::Only(Side::Left) => &self.lefts,
+            Balance::Only(Side::Right) => &self.rights,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Node<T> {
+    value: T,
+    left_count: usize,
+    right_count: usize,
+    priority: u64,
+    left: Option<Box<Node<T>>>,
+    right: Option<Box<Node<T>>>,
+    stats: Stats<T>,
+}
+
+impl<T: LocationId> Node<T> {
+    fn new(value: T, priority: u64) -> Node<T> {
+        Node {
+            value,
+            left_count: 0,
+            right_count: 0,
+            priority,
+            left: None,
+            right: None,
+            stats: Stats {
+                left_size: 0,
+                right_size: 0,
+                min: value,
+                max: value,
+                similarity: Some(0),
+                net: Profile::point(0),
+                lefts: Profile::point(0),
+                rights: Profile::point(0),
+            },
+        }
+    }
+
+    fn count(&self, side: Side) -> usize {
+        match side {
+            Side::Left => self.left_count,
+            Side::Right => self.right_count,
+        }
+    }
+
+    fn count_mut(&mut self, side: Side) -> &mut usize {
+        match side {
+            Side::Left => &mut self.left_count,
+            Side::Right => &mut self.right_count,
+        }
+    }
+
+    fn balance(&self, balance: Balance) -> i64 {
+        match balance {
+            Balance::Net => self.left_count as i64 - self.right_count as i64,
+            Balance::Only(side) => self.count(side) as i64,
+        }
+    }
+
+    // The ID times both counts. Multiplying by the counts one at a time never
+    // overflows unless the result does, as neither count is 0.
+    fn similarity(&self) -> Option<i128> {
+        if self.left_count == 0 || self.right_count == 0 {
+            return Some(0);
+        }
+        self.value
+            .widen()
+            .checked_mul(self.left_count as i128)?
+            .checked_mul(self.right_count as i128)
+    }
+
+    fn update(&mut self) {
+        let point = |balance| Profile::point(self.balance(balance));
+        let mut stats = Stats {
+            left_size: self.left_count,
+            right_size: self.right_count,
+            min: self.value,
+            max: self.value,
+            similarity: self.similarity(),
+            net: point(Balance::Net),
+            lefts: point(Balance::Only(Side::Left)),
+            rights: point(Balance::Only(Side::Right)),
+        };
+        let add = |first: Option<i128>, second: Option<i128>| first?.checked_add(second?);
+
+        if let Some(left) = &self.left {
+            let left = &left.stats;
+            let gap = left.max.distance(self.value);
+            stats = Stats {
+                left_size: left.left_size + stats.left_size,
+                right_size: left.right_size + stats.right_size,
+                min: left.min,
+                max: stats.max,
+                similarity: add(left.similarity, stats.similarity),
+                net: left.net.join(gap, stats.net, 0),
+                lefts: left.lefts.join(gap, stats.lefts, 0),
+                rights: left.rights.join(gap, stats.rights, 0),
+            };
+        }
+        if let Some(right) = &self.right {
+            let right = &right.stats;
+            let gap = self.value.distance(right.min);
+            let span = right.span();
+            stats = Stats {
+                left_size: stats.left_size + right.left_size,
+                right_size: stats.right_size + right.right_size,
+                min: stats.min,
+                max: right.max,
+                similarity: add(stats.similarity, right.similarity),
+                net: stats.net.join(gap, right.net, span),
+                lefts: stats.lefts.join(gap, right.lefts, span),
+                rights: stats.rights.join(gap, right.rights, span),
+            };
+        }
+        self.stats = stats;
+    }
+}
+
+fn priority<T>(node: &Option<Box<Node<T>>>) -> u64 {
+    node.as_ref().map_or(0, |node| node.priority)
+}
+
+fn rotate_right<T: LocationId>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    let mut new_root = node.left.take().unwrap();
+    node.left = new_root.right.take();
+    node.update();
+    new_root.right = Some(node);
+    new_root.update();
+    new_root
+}
+
+fn rotate_left<T: LocationId>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    let mut new_root = node.right.take().unwrap();
+    node.right = new_root.left.take();
+    node.update();
+    new_root.left = Some(node);
+    new_root.update();
+    new_root
+}
+
+fn insert_node<T: LocationId>(
+    node: Option<Box<Node<T>>>,
+    value: T,
+    side: Side,
+    new_priority: u64,
+) -> Box<Node<T>> {
+    let mut node = match node {
+        Some(node) => node,
+        None => {
+            let mut node = Box::new(Node::new(value, new_priority));
+            *node.count_mut(side) += 1;
+            node.update();
+            return node;
+        }
+    };
+
+    if value == node.value {
+        *node.count_mut(side) += 1;
+    } else if value < node.value {
+        node.left = Some(insert_node(node.left.take(), value, side, new_priority));
+        if priority(&node.left) > node.priority {
+            node = rotate_right(node);
+        }
+    } else {
+        node.right = Some(insert_node(node.right.take(), value, side, new_priority));
+        if priority(&node.right) > node.priority {
+            node = rotate_left(node);
+        }
+    }
+    node.update();
+    node
+}
+
+fn merge_nodes<T: LocationId>(
+    left: Option<Box<Node<T>>>,
+    right: Option<Box<Node<T>>>,
+) -> Option<Box<Node<T>>> {
+    match (left, right) {
+        (None, right) => right,
+        (left, None) => left,
+        (Some(mut left), Some(mut right)) => {
+            if left.priority > right.priority {
+                left.right = merge_nodes(left.right.take(), Some(right));
+                left.update();
+                Some(left)
+            } else {
+                right.left = merge_nodes(Some(left), right.left.take());
+                right.update();
+                Some(right)
+            }
+        }
+    }
+}
+
+fn remove_node<T: LocationId>(
+    node: Option<Box<Node<T>>>,
+    value: T,
+    side: Side,
+    removed: &mut bool,
+) -> Option<Box<Node<T>>> {
+    let mut node = node?;
+
+    if value == node.value {
+        if node.count(side) == 0 {
+            return Some(node);
+        }
+        *removed = true;
+        *node.count_mut(side) -= 1;
+        if node.left_count + node.right_count == 0 {
+            return merge_nodes(node.left.take(), node.right.take());
+        }
+    } else if value < node.value {
+        node.left = remove_node(node.left.take(), value, side, removed);
+    } else {
+        node.right = remove_node(node.right.take(), value, side, removed);
+    }
+    node.update();
+    Some(node)
+}
+
+// Lowest and highest ID a gap may touch, None for no limit.
+type Bounds<T> = (Option<T>, Option<T>);
+
+// Sum of gap times |balance| over the gaps of the subtree that lie within
+// `bounds`, the balance being `start` before the subtree. `visits` counts the
+// nodes descended into. None if the sum overflows.
+fn spread<T: LocationId>(
+    node: &Node<T>,
+    start: i64,
+    bounds: Bounds<T>,
+    balance: Balance,
+    visits: &mut usize,
+) -> Option<i128> {
+    let stats = &node.stats;
+    let above_low = |value: T| bounds.0.is_none_or(|low| low <= value);
+    let below_high = |value: T| bounds.1.is_none_or(|high| value <= high);
+    if !above_low(stats.max) || !below_high(stats.min) {
+        return Some(0);
+    }
+    let profile = stats.profile(balance);
+    let range = match profile.range {
+        Some(range) => range,
+        None => return Some(0),
+    };
+    if above_low(stats.min) && below_high(stats.max) {
+        let signed = || times(stats.span(), start)?.checked_add(profile.area?);
+        if start + range.0 >= 0 {
+            return signed();
+        }
+        if start + range.1 <= 0 {
+            return signed()?.checked_neg();
+        }
+    }
+
+    *visits += 1;
+    let within = |from: T, to: T| above_low(from) && below_high(to);
+    let mut total: i128 = 0;
+    let mut current = start;
+    if let Some(left) = &node.left {
+        total = total.checked_add(spread(left, current, bounds, balance, visits)?)?;
+        current += left.stats.profile(balance).total;
+        if within(left.stats.max, node.value) {
+            let gap = left.stats.max.distance(node.value);
+            total = total.checked_add(times(gap, current.abs())?)?;
+        }
+    }
+    current += node.balance(balance);
+    if let Some(right) = &node.right {
+        if within(node.value, right.stats.min) {
+            let gap = node.value.distance(right.stats.min);
+            total = total.checked_add(times(gap, current.abs())?)?;
+        }
+        total = total.checked_add(spread(right, current, bounds, balance, visits)?)?;
+    }
+    Some(total)
+}
+
+impl<T: LocationId> Default for LocationLists<T> {
+    fn default() -> Self {
+        LocationLists {
+            root: None,
+            seed: 0x9E37_79B9_7F4A_7C15,
+            distance: Cell::new(None),
+        }
+    }
+}
+
+impl<T: LocationId> LocationLists<T> {
+    pub fn new() -> LocationLists<T> {
+        LocationLists::default()
+    }
+
+    pub fn from_columns(col1: &[T], col2: &[T]) -> LocationLists<T> {
+        let mut lists = LocationLists::new();
+        col1.iter().for_each(|&id| lists.insert_left(id));
+        col2.iter().for_each(|&id| lists.insert_right(id));
+        lists
+    }
+
+    fn size(&self, side: Side) -> usize {
+        self.root.as_ref().map_or(0, |root| root.stats.size(side))
+    }
+
+    pub fn left_len(&self) -> usize {
+        self.size(Side::Left)
+    }
+
+    pub fn right_len(&self) -> usize {
+        self.size(Side::Right)
+    }
+
+    fn next_priority(&mut self) -> u64 {
+        // xorshift64, only used to keep the treap balanced
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        self.seed
+    }
+
+    #[cfg(test)]
+    fn count(&self, id: T, side: Side) -> usize {
+        let mut current = &self.root;
+        while let Some(node) = current {
+            if id == node.value {
+                return node.count(side);
+            }
+            current = if id < node.value {
+                &node.left
+            } else {
+                &node.right
+            };
+        }
+        0
+    }
+
+    // The `rank`-th smallest ID of one column, counting from 0.
+    fn select(&self, side: Side, mut rank: usize) -> Option<T> {
+        let mut current = &self.root;
+        while let Some(node) = current {
+            let left_size = node.left.as_ref().map_or(0, |left| left.stats.size(side));
+            if rank < left_size {
+                current = &node.left;
+            } else if rank < left_size + node.count(side) {
+                return Some(node.value);
+            } else {
+                rank -= left_size + node.count(side);
+                current = &node.right;
+            }
+        }
+        None
+    }
+
+    fn insert(&mut self, id: T, side: Side) {
+        let new_priority = self.next_priority();
+        self.root = Some(insert_node(self.root.take(), id, side, new_priority));
+        self.distance.set(None);
+    }
+
+    fn remove(&mut self, id: T, side: Side) -> bool {
+        let mut removed = false;
+        self.root = remove_node(self.root.take(), id, side, &mut removed);
+        if removed {
+            self.distance.set(None);
+        }
+        removed
+    }
+
+    pub fn insert_left(&mut self, id: T) {
+        self.insert(id, Side::Left);
+    }
+
+    pub fn insert_right(&mut self, id: T) {
+        self.insert(id, Side::Right);
+    }
+
+    // Returns false if the ID was not present in the left column.
+    pub fn remove_left(&mut self, id: T) -> bool {
+        self.remove(id, Side::Left)
+    }
+
+    // Returns false if the ID was not present in the right column.
+    pub fn remove_right(&mut self, id: T) -> bool {
+        self.remove(id, Side::Right)
+    }
+
+    // Summed in an i128 like `get_similarity_score`.
+    pub fn similarity_score(&self) -> Result<i128, OverflowError> {
+        match &self.root {
+            Some(root) => root.stats.similarity.ok_or(OverflowError::Similarity),
+            None => Ok(0),
+        }
+    }
+
+    // Sum of distances between the i-th smallest IDs of both columns. When the
+    // columns differ in length only the first min(left, right) pairs count.
+    pub fn total_distance(&self) -> Result<u128, OverflowError> {
+        if let Some(distance) = self.distance.get() {
+            return distance;
+        }
+        let distance = self.measure_distance(&mut 0).ok_or(OverflowError::Distance);
+        self.distance.set(Some(distance));
+        distance
+    }
+
+    fn measure_distance(&self, visits: &mut usize) -> Option<u128> {
+        let root = match &self.root {
+            Some(root) => root,
+            None => return Some(0),
+        };
+        let (left_len, right_len) = (self.left_len(), self.right_len());
+        if left_len == right_len {
+            let distance = spread(root, 0, (None, None), Balance::Net, visits)?;
+            return u128::try_from(distance).ok();
+        }
+
+        // Past the last paired ID of the longer column its cumulative count
+        // stays at the number of pairs, so the area there is the distance of
+        // every later ID of the shorter column to that last paired ID.
+        let (longer, shorter, pairs) = if left_len > right_len {
+            (Side::Left, Side::Right, right_len)
+        } else {
+            (Side::Right, Side::Left, left_len)
+        };
+        if pairs == 0 {
+            return Some(0);
+        }
+        let last = self.select(longer, pairs - 1)?;
+        let paired = spread(root, 0, (None, Some(last)), Balance::Net, visits)?;
+        let rest = spread(
+            root,
+            -(pairs as i64),
+            (Some(last), None),
+            Balance::Only(shorter),
+            visits,
+        )?;
+        u128::try_from(paired.checked_add(rest)?).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{get_similarity_score, total_distance};
+    use proptest::prelude::*;
+
+    fn recompute(col1: &[i32], col2: &[i32]) -> (u128, i128) {
+        let mut col1 = col1.to_vec();
+        let mut col2 = col2.to_vec();
+        col1.sort();
+        col2.sort();
+        let similarity = col1
+            .iter()
+            .map(|&id| id as i128 * col2.iter().filter(|&&other| other == id).count() as i128)
+            .sum();
+        (total_distance(&col1, &col2).unwrap(), similarity)
+    }
+
+    #[derive(Debug, Clone)]
+    enum Operation {
+        InsertLeft(i32),
+        InsertRight(i32),
+        RemoveLeft(i32),
+        RemoveRight(i32),
+    }
+
+    fn operation() -> impl Strategy<Value = Operation> {
+        prop_oneof![
+            (-10..10i32).prop_map(Operation::InsertLeft),
+            (-10..10i32).prop_map(Operation::InsertRight),
+            (-10..10i32).prop_map(Operation::RemoveLeft),
+            (-10..10i32).prop_map(Operation::RemoveRight),
+        ]
+    }
+
+    #[test]
+    fn test_example_lists() {
+        let lists = LocationLists::from_columns(&[3, 4, 2, 1, 3, 3], &[4, 3, 5, 3, 9, 3]);
+
+        assert_eq!(lists.total_distance(), Ok(11));
+        assert_eq!(lists.similarity_score(), Ok(31));
+        assert_eq!(
+            lists.similarity_score(),
+            get_similarity_score::<u32>(&[3, 4, 2, 1, 3, 3], &[4, 3, 5, 3, 9, 3])
+        );
+    }
+
+    #[test]
+    fn test_remove_missing_id() {
+        let mut lists = LocationLists::<u32>::from_columns(&[1, 2], &[2, 3]);
+
+        assert!(!lists.remove_left(3));
+        assert!(!lists.remove_right(1));
+        assert_eq!(lists.left_len(), 2);
+        assert_eq!(lists.right_len(), 2);
+    }
+
+    #[test]
+    fn test_select_and_count() {
+        let lists = LocationLists::<u32>::from_columns(&[5, 1, 3, 3, 9, 1, 3], &[3, 7]);
+
+        let lefts: Vec<u32> = (0..7)
+            .map(|rank| lists.select(Side::Left, rank).unwrap())
+            .collect();
+        assert_eq!(lefts, vec![1, 1, 3, 3, 3, 5, 9]);
+        assert_eq!(lists.select(Side::Left, 7), None);
+        assert_eq!(lists.select(Side::Right, 1), Some(7));
+        assert_eq!(lists.count(3, Side::Left), 3);
+        assert_eq!(lists.count(3, Side::Right), 1);
+    }
+
+    #[test]
+    fn test_distance_after_low_inserts_stays_cheap() {
+        // Inserting below every other ID shifts every pair, which a walk over
+        // the pairs would have to redo on each query.
+        let col1: Vec<u32> = (0..20_000).map(|i| 1_000 + 2 * i).collect();
+        let col2: Vec<u32> = (0..20_000).map(|i| 1_001 + 2 * i).collect();
+        let mut lists = LocationLists::from_columns(&col1, &col2);
+
+        for round in 0..200 {
+            lists.insert_left(round);
+            lists.insert_right(round + 1);
+            let mut visits = 0;
+            lists.measure_distance(&mut visits);
+            assert!(visits <= 100, "{} visits in round {}", visits, round);
+        }
+        assert_eq!(lists.total_distance(), Ok(20_000 + 200));
+    }
+
+    #[test]
+    fn test_distance_of_interleaved_columns_walks_the_tree() {
+        // Left 0, 3, 4, 7, 8, ... and right 1, 2, 5, 6, ...: the balance
+        // changes sign at every other ID, which is the worst case for a query.
+        let pairs = 2_000;
+        let col1: Vec<u32> = (0..pairs)
+            .map(|i| 2 * i + if i % 2 == 0 { 0 } else { 1 })
+            .collect();
+        let col2: Vec<u32> = (0..pairs)
+            .map(|i| 2 * i + if i % 2 == 0 { 1 } else { 0 })
+            .collect();
+        let lists = LocationLists::from_columns(&col1, &col2);
+
+        let mut visits = 0;
+        assert_eq!(lists.measure_distance(&mut visits), Some(pairs as u128));
+        assert!(
+            visits >= pairs as usize / 2 && visits <= 2 * pairs as usize,
+            "{} visits",
+            visits
+        );
+    }
+
+    #[test]
+    fn test_wide_and_negative_ids() {
+        let mut lists = LocationLists::from_columns(&[i64::MIN, 0], &[i64::MAX, 0]);
+        assert_eq!(lists.total_distance(), Ok(u64::MAX as u128));
+        assert_eq!(lists.similarity_score(), Ok(0));
+
+        lists.insert_right(i64::MIN);
+        lists.insert_left(i64::MAX);
+        assert_eq!(lists.total_distance(), Ok(0));
+        assert_eq!(lists.similarity_score(), Ok(-1));
+    }
+
+    #[test]
+    fn test_overflow_is_reported_until_removed() {
+        let half = i128::MAX / 2;
+        let mut lists = LocationLists::from_columns(&[half, 1], &[half, 1]);
+        assert_eq!(lists.similarity_score(), Ok(half + 1));
+
+        lists.insert_right(half);
+        assert_eq!(lists.similarity_score(), Ok(2 * half + 1));
+        lists.insert_left(half);
+        assert_eq!(lists.similarity_score(), Err(OverflowError::Similarity));
+        lists.remove_right(half);
+        assert_eq!(lists.similarity_score(), Ok(2 * half + 1));
+
+        let wide = LocationLists::from_columns(&[i128::MIN, i128::MIN], &[i128::MAX, i128::MAX]);
+        assert_eq!(wide.total_distance(), Err(OverflowError::Distance));
+    }
+
+    proptest! {
+        #[test]
+        fn prop_matches_full_recomputation(operations in prop::collection::vec(operation(), 0..200)) {
+            let mut lists = LocationLists::new();
+            let mut col1: Vec<i32> = vec![];
+            let mut col2: Vec<i32> = vec![];
+
+            for operation in operations {
+                match operation {
+                    Operation::InsertLeft(id) => {
+                        lists.insert_left(id);
+                        col1.push(id);
+                    }
+                    Operation::InsertRight(id) => {
+                        lists.insert_right(id);
+                        col2.push(id);
+                    }
+                    Operation::RemoveLeft(id) => {
+                        let position = col1.iter().position(|&other| other == id);
+                        prop_assert_eq!(lists.remove_left(id), position.is_some());
+                        if let Some(position) = position {
+                            col1.swap_remove(position);
+                        }
+                    }
+                    Operation::RemoveRight(id) => {
+                        let position = col2.iter().position(|&other| other == id);
+                        prop_assert_eq!(lists.remove_right(id), position.is_some());
+                        if let Some(position) = position {
+                            col2.swap_remove(position);
+                        }
+                    }
+                }
+
+                let (distance, similarity) = recompute(&col1, &col2);
+                prop_assert_eq!(lists.total_distance(), Ok(distance));
+                prop_assert_eq!(lists.similarity_score(), Ok(similarity));
+                prop_assert_eq!(lists.left_len(), col1.len());
+                prop_assert_eq!(lists.right_len(), col2.len());
+            }
+        }
+
+        // A balance that never changes sign is summed without descending
+        // into the tree at all.
+        #[test]
+        fn prop_one_signed_balance_is_not_walked(
+            ids in prop::collection::vec(0..1_000_000u32, 1..300),
+            shift in 1..1_000u32,
+        ) {
+            let shifted: Vec<u32> = ids.iter().map(|&id| id + shift).collect();
+            let lists = LocationLists::from_columns(&ids, &shifted);
+
+            let mut visits = 0;
+            let distance = lists.measure_distance(&mut visits);
+            prop_assert_eq!(distance, Some(ids.len() as u128 * shift as u128));
+            prop_assert_eq!(visits, 0);
+        }
+    }
+}
@@ -1,20 +1,69 @@
-
 use std::env;
-use std::fs::File;
-use std::io::{self, BufRead};
-use std::path::Path;
+
+use day_01::{
+    get_similarity_score, jaccard_similarity, max_distance, median_distance, multiset_overlap,
+    narrow_ids, read_and_parse_file, squared_distance, total_distance, IntType, LocationId, Metric,
+};
 
 fn main() {
     // Get the file path from command-line arguments
     let args: Vec<String> = env::args().collect();
     if args.len() < 2 {
-        eprintln!("Usage: {} <file_path>", args[0]);
+        eprintln!(
+            "Usage: {} <file_path> [--int-type auto|u32|i32|i64|u64|i128] [--metric <metric>]",
+            args[0]
+        );
+        eprintln!("Metrics: distance, squared, max, median, similarity, jaccard, overlap");
         std::process::exit(1);
     }
     let file_path = &args[1];
 
+    let mut int_type = None;
+    let mut metric = None;
+    let mut options = args[2..].iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--int-type" => {
+                let value = options.next().map(String::as_str).unwrap_or_default();
+                if value != "auto" {
+                    int_type = Some(value.parse::<IntType>().unwrap_or_else(|err| {
+                        eprintln!("{}", err);
+                        std::process::exit(1);
+                    }));
+                }
+            }
+            "--metric" => {
+                let value = options.next().map(String::as_str).unwrap_or_default();
+                metric = Some(value.parse::<Metric>().unwrap_or_else(|err| {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }));
+            }
+            _ => {
+                eprintln!("Unknown option: {}", option);
+                std::process::exit(1);
+            }
+        }
+    }
+
     // Open the file and read its content
-    let (mut col1, mut col2) = read_and_parse_file(file_path).expect("Failed to read and parse file");
+    let (col1, col2) =
+        read_and_parse_file::<i128>(file_path).expect("Failed to read and parse file");
+    let int_type = int_type.unwrap_or_else(|| IntType::detect(&col1, &col2));
+    println!("Integer type: {:?}", int_type);
+
+    match int_type {
+        IntType::U32 => solve::<u32>(&col1, &col2, metric),
+        IntType::I32 => solve::<i32>(&col1, &col2, metric),
+        IntType::I64 => solve::<i64>(&col1, &col2, metric),
+        IntType::U64 => solve::<u64>(&col1, &col2, metric),
+        IntType::I128 => solve::<i128>(&col1, &col2, metric),
+    }
+}
+
+fn solve<T: LocationId>(col1: &[i128], col2: &[i128], metric: Option<Metric>) {
+    let mut col1 = narrow_ids::<T>(col1).expect("Failed to read and parse file");
+    let mut col2 = narrow_ids::<T>(col2).expect("Failed to read and parse file");
 
     // Print the vectors
     println!("Column 1: length {:?}", col1.len());
@@ -23,50 +72,44 @@ fn main() {
     col1.sort();
     col2.sort();
 
-    println!("==================== Part 1 ====================");
-    println!("Total distance: {}", total_distance(&col1, &col2));
+    if let Some(metric) = metric {
+        print_metric(metric, &col1, &col2);
+        return;
+    }
 
     println!("==================== Part 1 ====================");
-    println!("Similarity score: {}", get_similarity_score(col1, col2));
-}
-
-fn read_and_parse_file(file_path: &str) -> io::Result<(Vec<u32>, Vec<u32>)> {
-    let path = Path::new(file_path);
-    let file = File::open(&path)?;
-    let reader = io::BufReader::new(file);
-
-    let mut col1: Vec<u32> = Vec::new();
-    let mut col2: Vec<u32> = Vec::new();
-
-    for line in reader.lines() {
-        let line = line?;
-        let numbers: Vec<&str> = line.split_whitespace().collect();
-        if numbers.len() == 2 {
-            if let (Ok(num1), Ok(num2)) = (numbers[0].parse::<u32>(), numbers[1].parse::<u32>()) {
-                col1.push(num1);
-                col2.push(num2);
-            }
-        }
+    match total_distance(&col1, &col2) {
+        Ok(distance) => println!("Total distance: {}", distance),
+        Err(err) => eprintln!("Total distance: {}", err),
     }
 
-    Ok((col1, col2))
-}
-
-fn total_distance(places_1: &Vec<u32>, places_2: &Vec<u32>) -> u64 {
-    (0..places_1.len() as usize)
-        .map(|i| {
-            let place_1 = places_1[i] as i64;
-            let place_2 = places_2[i] as i64;
-            let distance = (place_1 - place_2).abs() as u64;
-            distance
-        })
-        .sum()
+    println!("==================== Part 2 ====================");
+    match get_similarity_score(&col1, &col2) {
+        Ok(score) => println!("Similarity score: {}", score),
+        Err(err) => eprintln!("Similarity score: {}", err),
+    }
 }
 
-fn get_similarity_score(places_1: Vec<u32>, places_2: Vec<u32>) -> u32 {
-    places_1.iter().map(|number| *number * count_number(*number, &places_2)).sum()
+fn print_metric<T: LocationId>(metric: Metric, col1: &[T], col2: &[T]) {
+    match metric {
+        Metric::Distance => match total_distance(col1, col2) {
+            Ok(distance) => println!("Total distance: {}", distance),
+            Err(err) => eprintln!("Total distance: {}", err),
+        },
+        Metric::SquaredDistance => match squared_distance(col1, col2) {
+            Ok(distance) => println!("Squared distance: {}", distance),
+            Err(err) => eprintln!("Squared distance: {}", err),
+        },
+        Metric::MaxDistance => println!("Max distance: {}", max_distance(col1, col2)),
+        Metric::MedianDistance => match median_distance(col1, col2) {
+            Some(distance) => println!("Median distance: {}", distance),
+            None => println!("Median distance: no pairs"),
+        },
+        Metric::Similarity => match get_similarity_score(col1, col2) {
+            Ok(score) => println!("Similarity score: {}", score),
+            Err(err) => eprintln!("Similarity score: {}", err),
+        },
+        Metric::Jaccard => println!("Jaccard similarity: {}", jaccard_similarity(col1, col2)),
+        Metric::Overlap => println!("Multiset overlap: {}", multiset_overlap(col1, col2)),
+    }
 }
-
-fn count_number(number: u32, places: &Vec<u32>) -> u32 {
-    places.iter().filter(|&place| *place == number).count() as u32
-}
\ No newline at end of file
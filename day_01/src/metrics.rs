@@ -0,0 +1,157 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use crate::{LocationId, OverflowError};
+
+// Alternative ways to compare the two location lists. Like `total_distance`,
+// all of the functions below expect both columns to be sorted.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Distance,
+    SquaredDistance,
+    MaxDistance,
+    MedianDistance,
+    Similarity,
+    Jaccard,
+    Overlap,
+}
+
+impl FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "distance" => Ok(Metric::Distance),
+            "squared" => Ok(Metric::SquaredDistance),
+            "max" => Ok(Metric::MaxDistance),
+            "median" => Ok(Metric::MedianDistance),
+            "similarity" => Ok(Metric::Similarity),
+            "jaccard" => Ok(Metric::Jaccard),
+            "overlap" => Ok(Metric::Overlap),
+            _ => Err(format!("Unsupported metric: {}", s)),
+        }
+    }
+}
+
+fn pair_distances<'a, T: LocationId>(
+    places_1: &'a [T],
+    places_2: &'a [T],
+) -> impl Iterator<Item = u128> + 'a {
+    places_1
+        .iter()
+        .zip(places_2.iter())
+        .map(|(&place_1, &place_2)| place_1.distance(place_2))
+}
+
+pub fn squared_distance<T: LocationId>(
+    places_1: &[T],
+    places_2: &[T],
+) -> Result<u128, OverflowError> {
+    pair_distances(places_1, places_2).try_fold(0u128, |total, distance| {
+        distance
+            .checked_mul(distance)
+            .and_then(|squared| total.checked_add(squared))
+            .ok_or(OverflowError::SquaredDistance)
+    })
+}
+
+// Chebyshev distance, 0 for empty lists.
+pub fn max_distance<T: LocationId>(places_1: &[T], places_2: &[T]) -> u128 {
+    pair_distances(places_1, places_2).max().unwrap_or(0)
+}
+
+// Median of the pair distances; for an even number of pairs this is the mean
+// of the two middle ones. None for empty lists.
+pub fn median_distance<T: LocationId>(places_1: &[T], places_2: &[T]) -> Option<f64> {
+    let mut distances: Vec<u128> = pair_distances(places_1, places_2).collect();
+    if distances.is_empty() {
+        return None;
+    }
+
+    let odd = distances.len() % 2 == 1;
+    let middle = distances.len() / 2;
+    let (lower, &mut upper, _) = distances.select_nth_unstable(middle);
+    if odd {
+        return Some(upper as f64);
+    }
+
+    let below = *lower.iter().max().unwrap();
+    Some((below as f64 + upper as f64) / 2.0)
+}
+
+// Size of the multiset intersection: every ID counts min(left, right) times.
+pub fn multiset_overlap<T: LocationId>(places_1: &[T], places_2: &[T]) -> usize {
+    let (overlap, _) = intersection_and_union(places_1, places_2);
+    overlap
+}
+
+// Multiset Jaccard index |A ∩ B| / |A ∪ B|, 1.0 when both lists are empty.
+pub fn jaccard_similarity<T: LocationId>(places_1: &[T], places_2: &[T]) -> f64 {
+    let (overlap, union) = intersection_and_union(places_1, places_2);
+    if union == 0 {
+        return 1.0;
+    }
+    overlap as f64 / union as f64
+}
+
+fn intersection_and_union<T: LocationId>(places_1: &[T], places_2: &[T]) -> (usize, usize) {
+    let (mut i, mut j) = (0, 0);
+    let mut overlap = 0;
+    while i < places_1.len() && j < places_2.len() {
+        match places_1[i].cmp(&places_2[j]) {
+            Ordering::Less => i += 1,
+            Ordering::Greater => j += 1,
+            Ordering::Equal => {
+                overlap += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    (overlap, places_1.len() + places_2.len() - overlap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> (Vec<u32>, Vec<u32>) {
+        let mut col1 = vec![3, 4, 2, 1, 3, 3];
+        let mut col2 = vec![4, 3, 5, 3, 9, 3];
+        col1.sort();
+        col2.sort();
+        (col1, col2)
+    }
+
+    #[test]
+    fn test_distance_metrics() {
+        let (col1, col2) = example();
+
+        assert_eq!(squared_distance(&col1, &col2), Ok(35));
+        assert_eq!(max_distance(&col1, &col2), 5);
+        assert_eq!(median_distance(&col1, &col2), Some(1.5));
+        assert_eq!(median_distance(&col1[..5], &col2[..5]), Some(1.0));
+        assert_eq!(median_distance::<u32>(&[], &[]), None);
+    }
+
+    #[test]
+    fn test_squared_distance_overflow() {
+        let col1: Vec<i128> = vec![0];
+        let col2: Vec<i128> = vec![i128::MAX];
+
+        assert_eq!(
+            squared_distance(&col1, &col2),
+            Err(OverflowError::SquaredDistance)
+        );
+    }
+
+    #[test]
+    fn test_overlap_metrics() {
+        let (col1, col2) = example();
+
+        assert_eq!(multiset_overlap(&col1, &col2), 4);
+        assert_eq!(jaccard_similarity(&col1, &col2), 0.5);
+        assert_eq!(jaccard_similarity::<u32>(&[], &[]), 1.0);
+    }
+}
@@ -0,0 +1,256 @@
+use crate::{is_safe_report, is_safe_report_problem_dampener, SafetyPolicy};
+
+// The single removal of the puzzle has a linear time check, larger
+// tolerances fall back to the general dampener.
+pub fn is_safe_with_tolerance(report: &[i32], policy: &SafetyPolicy, tolerance: usize) -> bool {
+    match tolerance {
+        0 => is_safe_report(report, policy),
+        1 => is_safe_report_problem_dampener(report, policy),
+        _ => is_safe_with_removals(report, policy, tolerance).is_some(),
+    }
+}
+
+// Finds the smallest set of levels whose removal makes the report safe.
+// Returns the sorted indices to remove, or None if more than `max_removals`
+// levels would have to go.
+//
+// Keeping as many levels as possible is the same as finding the longest
+// subsequence that is safe on its own, which is an O(n^2) DP per allowed
+// direction.
+pub fn is_safe_with_removals(
+    report: &[i32],
+    policy: &SafetyPolicy,
+    max_removals: usize,
+) -> Option<Vec<usize>> {
+    if report.len() < 2 {
+        return Some(vec![]);
+    }
+
+    let kept = policy
+        .allowed_growth()
+        .iter()
+        .map(|&growing| longest_safe_subsequence(report, policy, growing))
+        .max_by_key(|kept| kept.len())
+        .unwrap_or_default();
+
+    // Any single level is safe on its own, so at least one is always kept.
+    if report.len() - kept.len() > max_removals {
+        return None;
+    }
+
+    let mut kept = kept.into_iter().peekable();
+    let removed = (0..report.len())
+        .filter(|&idx| {
+            if kept.peek() == Some(&idx) {
+                kept.next();
+                false
+            } else {
+                true
+            }
+        })
+        .collect();
+    Some(removed)
+}
+
+// Finds a level whose removal leaves the report safe, in O(n) time and
+// without allocating. A report that is already safe stays safe without its
+// last level, so this is Some for every safe report with at least one level.
+pub fn find_removable_level(report: &[i32], policy: &SafetyPolicy) -> Option<usize> {
+    policy
+        .allowed_growth()
+        .iter()
+        .find_map(|&growing| removable_level_in_direction(report, policy, growing))
+}
+
+fn removable_level_in_direction(
+    report: &[i32],
+    policy: &SafetyPolicy,
+    growing: bool,
+) -> Option<usize> {
+    let levels = report.len();
+    if levels == 0 {
+        return None;
+    }
+    let is_valid = |prev: usize, current: usize| {
+        policy.is_valid_directed_step(report[prev], report[current], growing)
+    };
+
+    // A step is named after the index it starts at. The prefix before the
+    // first bad step and the suffix after the last one are both valid.
+    let mut first_bad = None;
+    let mut last_bad = None;
+    for step in 0..levels - 1 {
+        if !is_valid(step, step + 1) {
+            first_bad.get_or_insert(step);
+            last_bad = Some(step);
+        }
+    }
+
+    let (first_bad, last_bad) = match (first_bad, last_bad) {
+        (Some(first_bad), Some(last_bad)) => (first_bad, last_bad),
+        _ => return Some(levels - 1),
+    };
+
+    // Removing level r drops steps r - 1 and r, so every bad step has to be
+    // one of them, and the new step bridging r has to be valid.
+    (last_bad..=first_bad + 1)
+        .filter(|&removed| removed < levels)
+        .find(|&removed| {
+            removed == 0 || removed == levels - 1 || is_valid(removed - 1, removed + 1)
+        })
+}
+
+fn longest_safe_subsequence(report: &[i32], policy: &SafetyPolicy, growing: bool) -> Vec<usize> {
+    // length[i] is the longest safe subsequence ending at level i, previous[i]
+    // the level kept right before it.
+    let mut length = vec![1; report.len()];
+    let mut previous: Vec<Option<usize>> = vec![None; report.len()];
+
+    for current in 1..report.len() {
+        for prev in 0..current {
+            if length[prev] + 1 > length[current]
+                && policy.is_valid_directed_step(report[prev], report[current], growing)
+            {
+                length[current] = length[prev] + 1;
+                previous[current] = Some(prev);
+            }
+        }
+    }
+
+    let mut idx = (0..report.len()).max_by_key(|&idx| length[idx]);
+    let mut kept = vec![];
+    while let Some(current) = idx {
+        kept.push(current);
+        idx = previous[current];
+    }
+    kept.reverse();
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{find_unsafe_level, Direction};
+    use proptest::prelude::*;
+
+    fn without(report: &[i32], removed: &[usize]) -> Vec<i32> {
+        report
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !removed.contains(idx))
+            .map(|(_, &level)| level)
+            .collect()
+    }
+
+    // Smallest number of removals found by trying every subset of levels.
+    fn brute_force_removals(report: &[i32], policy: &SafetyPolicy) -> usize {
+        (0u32..1 << report.len())
+            .filter_map(|mask| {
+                let removed: Vec<usize> = (0..report.len())
+                    .filter(|idx| mask & (1 << idx) != 0)
+                    .collect();
+                find_unsafe_level(&without(report, &removed), policy)
+                    .is_none()
+                    .then_some(removed.len())
+            })
+            .min()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_is_safe_with_removals() {
+        let policy = SafetyPolicy::default();
+
+        assert_eq!(
+            is_safe_with_removals(&[7, 6, 4, 2, 1], &policy, 0),
+            Some(vec![])
+        );
+        assert_eq!(
+            is_safe_with_removals(&[1, 3, 2, 4, 5], &policy, 1),
+            Some(vec![2])
+        );
+        assert_eq!(
+            is_safe_with_removals(&[8, 6, 4, 4, 1], &policy, 1),
+            Some(vec![3])
+        );
+        assert_eq!(is_safe_with_removals(&[1, 2, 7, 8, 9], &policy, 1), None);
+        assert_eq!(
+            is_safe_with_removals(&[1, 2, 7, 8, 9], &policy, 2),
+            Some(vec![0, 1])
+        );
+        assert_eq!(
+            is_safe_with_removals(&[24, 21, 22, 24, 25], &policy, 1),
+            Some(vec![0])
+        );
+    }
+
+    #[test]
+    fn test_find_removable_level() {
+        let policy = SafetyPolicy::default();
+
+        assert_eq!(find_removable_level(&[7, 6, 4, 2, 1], &policy), Some(4));
+        assert_eq!(find_removable_level(&[1, 3, 2, 4, 5], &policy), Some(1));
+        assert_eq!(find_removable_level(&[8, 6, 4, 4, 1], &policy), Some(2));
+        assert_eq!(
+            find_removable_level(&[24, 21, 22, 24, 25], &policy),
+            Some(0)
+        );
+        assert_eq!(find_removable_level(&[1, 2, 7, 8, 9], &policy), None);
+        assert_eq!(find_removable_level(&[9, 7, 6, 2, 1], &policy), None);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_matches_brute_force(
+            report in prop::collection::vec(0..10i32, 0..10),
+            max_removals in 0..4usize,
+            allow_plateaus in any::<bool>(),
+            direction in prop_oneof![
+                Just(Direction::Either),
+                Just(Direction::Increasing),
+                Just(Direction::Decreasing),
+            ],
+        ) {
+            let policy = SafetyPolicy {
+                allow_plateaus,
+                direction,
+                ..SafetyPolicy::default()
+            };
+            let expected = brute_force_removals(&report, &policy);
+
+            match is_safe_with_removals(&report, &policy, max_removals) {
+                Some(removed) => {
+                    prop_assert_eq!(removed.len(), expected);
+                    prop_assert!(find_unsafe_level(&without(&report, &removed), &policy).is_none());
+                }
+                None => prop_assert!(expected > max_removals),
+            }
+        }
+
+        #[test]
+        fn prop_single_removal_matches_brute_force(
+            report in prop::collection::vec(0..10i32, 1..12),
+            allow_plateaus in any::<bool>(),
+            direction in prop_oneof![
+                Just(Direction::Either),
+                Just(Direction::Increasing),
+                Just(Direction::Decreasing),
+            ],
+        ) {
+            let policy = SafetyPolicy {
+                allow_plateaus,
+                direction,
+                ..SafetyPolicy::default()
+            };
+            let brute_force = (0..report.len())
+                .any(|idx| find_unsafe_level(&without(&report, &[idx]), &policy).is_none());
+
+            match find_removable_level(&report, &policy) {
+                Some(removed) => {
+                    prop_assert!(find_unsafe_level(&without(&report, &[removed]), &policy).is_none());
+                }
+                None => prop_assert!(!brute_force),
+            }
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+pub mod dampener;
+pub mod parse;
+pub mod policy;
+pub mod repair;
+pub mod stream;
+pub mod summary;
+pub mod verdict;
+
+pub use dampener::{find_removable_level, is_safe_with_removals, is_safe_with_tolerance};
+pub use parse::{InvalidLevel, ParseMode, RejectedLine};
+pub use policy::{Direction, SafetyPolicy};
+pub use repair::{suggest_repairs, Repair, RepairStrategy};
+pub use stream::{count_reports, count_reports_parallel, for_each_report, ReportCounts};
+pub use summary::ReportSummary;
+pub use verdict::{
+    evaluate_report, render_verdict, SafetyVerdict, Violation, ViolationKind, Violations,
+};
+
+pub fn read_and_parse_reports(file_path: &str) -> io::Result<Vec<Vec<i32>>> {
+    let path = Path::new(file_path);
+    let file = File::open(path)?;
+    let reader = io::BufReader::new(file);
+
+    let mut reports = Vec::new();
+    for_each_report(reader, ParseMode::Strict, |levels| {
+        reports.push(levels.to_vec())
+    })?;
+
+    Ok(reports)
+}
+
+pub fn is_safe_report(report: &[i32], policy: &SafetyPolicy) -> bool {
+    find_unsafe_level(report, policy).is_none()
+}
+
+pub fn is_safe_report_problem_dampener(report: &[i32], policy: &SafetyPolicy) -> bool {
+    report.len() < 2 || find_removable_level(report, policy).is_some()
+}
+
+pub fn find_unsafe_level(report: &[i32], policy: &SafetyPolicy) -> Option<(usize, usize)> {
+    Violations::new(report, policy)
+        .next()
+        .map(|violation| violation.indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_unsafe_level_returns_none_on_safe_reports() {
+        let safe_reports = vec![
+            vec![1, 2, 3, 4, 5],
+            vec![7, 6, 4, 2, 1],
+            vec![1, 3, 6, 7, 9],
+            vec![21, 22, 24, 25, 26, 27],
+        ];
+
+        for report in safe_reports {
+            assert_eq!(
+                find_unsafe_level(&report, &SafetyPolicy::default()),
+                None,
+                "Report {:?} considered unsafe, but expected to be safe",
+                report
+            );
+        }
+    }
+
+    #[test]
+    fn test_find_unsafe_level_returns_returns_positions_for_unsafe_reports() {
+        let unsafe_reports = vec![
+            (vec![1, 2, 7, 8, 9], Some((1, 2))),
+            (vec![9, 7, 6, 2, 1], Some((2, 3))),
+            (vec![1, 3, 2, 4, 5], Some((1, 2))),
+            (vec![8, 6, 4, 4, 1], Some((2, 3))),
+        ];
+
+        for (report, expected_positions) in unsafe_reports {
+            assert_eq!(
+                find_unsafe_level(&report, &SafetyPolicy::default()),
+                expected_positions,
+                "Report {:?} considered unsafe, but expected to be safe",
+                report
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_safe_report_problem_dampener_can_tolerate_unsafe_level() {
+        let unsafe_reports = vec![vec![24, 21, 22, 24, 25, 26, 27]];
+
+        for report in unsafe_reports {
+            assert!(
+                is_safe_report_problem_dampener(&report, &SafetyPolicy::default()),
+                "Report {:?} considered unsafe, but expected to be safe",
+                report
+            );
+        }
+    }
+
+    #[test]
+    fn test_find_unsafe_level_with_custom_policy() {
+        let policy = SafetyPolicy {
+            min_step: 1,
+            max_step: 5,
+            allow_plateaus: true,
+            direction: Direction::Decreasing,
+        };
+
+        assert_eq!(find_unsafe_level(&[9, 9, 4, 4, 1], &policy), None);
+        assert_eq!(find_unsafe_level(&[9, 9, 10, 4], &policy), Some((1, 2)));
+        assert_eq!(find_unsafe_level(&[1, 2, 3], &policy), Some((0, 1)));
+        assert_eq!(find_unsafe_level(&[9, 3], &policy), Some((0, 1)));
+    }
+}
@@ -1,184 +1,222 @@
-use std::{env, vec};
+use std::env;
 use std::fs::File;
-use std::io::{self, BufRead};
-use std::path::Path;
+use std::io::{self, IsTerminal};
 
-const SAFETY_MIN_DIFF: i32 = 1;
-const SAFETY_MAX_DIFF: i32 = 3;
+use day_02::{
+    count_reports, count_reports_parallel, evaluate_report, for_each_report, render_verdict,
+    suggest_repairs, ParseMode, RejectedLine, ReportSummary, SafetyPolicy,
+};
+
+const PARALLEL_CHUNK_LINES: usize = 64 * 1024;
 
 fn main() {
     // Get the file path from command-line arguments
     let args: Vec<String> = env::args().collect();
     if args.len() < 2 {
-        eprintln!("Usage: {} <file_path>", args[0]);
+        eprintln!("Usage: {} <file_path> [options]", args[0]);
+        eprintln!("  --config <file>       read the safety policy from a config file");
+        eprintln!("  --min-step <n>        smallest allowed change between levels");
+        eprintln!("  --max-step <n>        largest allowed change between levels");
+        eprintln!("  --allow-plateaus      allow neighbouring levels to be equal");
+        eprintln!("  --direction <dir>     increasing, decreasing or either");
+        eprintln!("  --tolerance <k>       levels the problem dampener may remove (default 1)");
+        eprintln!("  --report              explain the verdict for every report");
+        eprintln!("  --parallel            classify reports on all cores");
+        eprintln!("  --repair              suggest level changes for every unsafe report");
+        eprintln!("  --lenient             skip lines with invalid levels and list them");
+        eprintln!("  --summary             print statistics over all reports");
+        eprintln!("  --format <fmt>        summary format, text or json");
         std::process::exit(1);
     }
     let file_path = &args[1];
 
-    let reports = read_and_parse_reports(file_path).expect("Failed to read and parse file");
+    let (policy, options) = parse_options(&args[2..]).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
 
-    println!("Reports found: {:?}", reports.len());
+    let tolerance = options.tolerance;
+    // Follow the NO_COLOR convention for plain output, and never write escape
+    // codes into a pipe or file
+    let color = env::var_os("NO_COLOR").is_none() && io::stdout().is_terminal();
 
-    println!("================= Part 1 =================");
-    let safe_reports_count = reports
-        .iter()
-        .filter(|report| is_safe_report(&report))
-        .count();
-    println!("Safe reports count: {}", safe_reports_count);
+    let file = File::open(file_path).expect("Failed to read and parse file");
+    let reader = io::BufReader::new(file);
 
-    println!("================= Part 2 =================");
-    let safe_reports_problem_dampener_count = reports
-        .iter()
-        .filter(|report| is_safe_report_problem_dampener(&report))
-        .count();
-    println!(
-        "Safe reports count: {}",
-        safe_reports_problem_dampener_count
-    );
-    for (idx, report) in reports.iter().enumerate() {
-        let is_safe = is_safe_report(report);
-        let is_tolerated_safe = is_safe_report_problem_dampener(report);
-        if !is_safe && !is_tolerated_safe {
-            println!("{} {}-{} - {:?}", idx, is_safe, is_tolerated_safe, report);
-        }
+    if options.report {
+        let mut idx = 0;
+        let rejected = for_each_report(reader, options.mode, |report| {
+            let verdict = evaluate_report(report, &policy, tolerance);
+            println!("{}: {}", idx, render_verdict(report, &verdict, color));
+            idx += 1;
+        })
+        .expect("Failed to read and parse file");
+        print_rejected(&rejected);
+        return;
     }
-}
-
-fn read_and_parse_reports(file_path: &str) -> io::Result<Vec<Vec<i32>>> {
-    let path = Path::new(file_path);
-    let file = File::open(&path)?;
-    let reader = io::BufReader::new(file);
 
-    let mut reports = Vec::new();
-    for line in reader.lines() {
-        let line = line?;
-        let mut levels = Vec::new();
-        for level_str in line.split_whitespace() {
-            match level_str.parse::<i32>() {
-                Ok(level) => levels.push(level),
-                Err(_) => {
-                    return Err(io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        format!("Invalid level: {}", level_str),
-                    ))
+    if options.repair {
+        let mut idx = 0;
+        let rejected = for_each_report(reader, options.mode, |report| {
+            let repairs = suggest_repairs(report, &policy);
+            if !repairs.is_empty() {
+                println!("{}: {:?}", idx, report);
+                for (rank, repair) in repairs.iter().enumerate() {
+                    println!(
+                        "  {}. {:?}: change levels {:?} (total adjustment {}) -> {:?}",
+                        rank + 1,
+                        repair.strategy,
+                        repair.changed,
+                        repair.total_adjustment,
+                        repair.report
+                    );
                 }
             }
-        }
-        if !levels.is_empty() {
-            reports.push(levels)
-        }
+            idx += 1;
+        })
+        .expect("Failed to read and parse file");
+        print_rejected(&rejected);
+        return;
     }
 
-    Ok(reports)
-}
-
-fn is_safe_report(report: &Vec<i32>) -> bool {
-    find_unsafe_level(report).is_none()
-}
-
-fn is_safe_report_problem_dampener(report: &Vec<i32>) -> bool {
-    let unsafe_level = find_unsafe_level(report);
-    match unsafe_level {
-        Some((unsafe_lvl_1_candidate, unsafe_lvl_2_candidate)) => {
-            let mut report_variant_1 = report.clone();
-            report_variant_1.remove(unsafe_lvl_1_candidate);
-
-            let mut report_variant_2 = report.clone();
-            report_variant_2.remove(unsafe_lvl_2_candidate);
-
-            let mut options = vec![
-                report_variant_1, report_variant_2,
-            ];
-
-            if unsafe_lvl_1_candidate == 1 {
-                let mut report_variant_3 = report.clone();
-                report_variant_3.remove(0);
-                options.push(report_variant_3);
-            }
-
-            for option in options {
-                if find_unsafe_level(&option).is_none() {
-                    return true;
-                }
-            }
-            
-            false
+    if options.summary {
+        let mut summary = ReportSummary::default();
+        let rejected = for_each_report(reader, options.mode, |report| {
+            summary.add(report, &policy, tolerance)
+        })
+        .expect("Failed to read and parse file");
+        summary.rejected = rejected;
+        if options.json {
+            println!("{}", summary.to_json());
+        } else {
+            print!("{}", summary.to_text());
         }
-        None => true,
+        return;
     }
-}
 
-fn find_unsafe_level(report: &Vec<i32>) -> Option<(usize, usize)> {
-    if report.len() < 2 {
-        return None;
+    // Both parts are answered in a single pass over the input
+    let counts = if options.parallel {
+        count_reports_parallel(
+            reader,
+            options.mode,
+            &policy,
+            tolerance,
+            PARALLEL_CHUNK_LINES,
+        )
+    } else {
+        count_reports(reader, options.mode, &policy, tolerance)
     }
+    .expect("Failed to read and parse file");
 
-    let first = report[0];
-    let second = report[1];
-    let growing = is_growing(first, second);
+    println!("Reports found: {:?}", counts.reports);
 
-    for current_idx in 1..report.len() {
-        let prev = report[current_idx - 1];
-        let current = report[current_idx];
+    println!("================= Part 1 =================");
+    println!("Safe reports count: {}", counts.safe);
 
-        if (prev - current).abs() < SAFETY_MIN_DIFF
-            || (prev - current).abs() > SAFETY_MAX_DIFF
-        {
-            return Some((current_idx - 1, current_idx));
-        }
+    println!("================= Part 2 =================");
+    println!("Safe reports count: {}", counts.tolerated);
+    print_rejected(&counts.rejected);
+}
 
-        if is_growing(prev, current) != growing {
-            return Some((current_idx - 1, current_idx));
-        }
+fn print_rejected(rejected: &[RejectedLine]) {
+    if rejected.is_empty() {
+        return;
+    }
+    println!("================= Rejected =================");
+    println!("Rejected lines: {}", rejected.len());
+    for rejected_line in rejected {
+        println!("{}", rejected_line);
     }
-
-    None
 }
 
-fn is_growing(x: i32, y: i32) -> bool {
-    x < y
+struct Options {
+    mode: ParseMode,
+    tolerance: usize,
+    report: bool,
+    parallel: bool,
+    repair: bool,
+    summary: bool,
+    json: bool,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_find_unsafe_level_returns_none_on_safe_reports() {
-        let safe_reports = vec![
-            vec![1, 2, 3, 4, 5],
-            vec![7, 6, 4, 2, 1],
-            vec![1, 3, 6, 7, 9],
-            vec![21, 22, 24, 25, 26, 27],
-        ];
-
-        for report in safe_reports {
-            assert_eq!(find_unsafe_level(&report), None, "Report {:?} considered unsafe, but expected to be safe", report);
-        }
-    }
-
-    #[test]
-    fn test_find_unsafe_level_returns_returns_positions_for_unsafe_reports() {
-        let unsafe_reports = vec![
-            (vec![1, 2, 7, 8, 9], Some((1, 2))),
-            (vec![9, 7, 6, 2, 1], Some((2, 3))),
-            (vec![1, 3, 2, 4, 5], Some((1, 2))),
-            (vec![8, 6, 4, 4, 1], Some((2, 3)))
-        ];
-
-        for (report, expected_positions) in unsafe_reports {
-            assert_eq!(find_unsafe_level(&report), expected_positions, "Report {:?} considered unsafe, but expected to be safe", report);
+// The config file is applied first so that flags can override single settings.
+fn parse_options(options: &[String]) -> Result<(SafetyPolicy, Options), String> {
+    let mut policy = match options.iter().position(|option| option == "--config") {
+        Some(idx) => {
+            let config_path = options.get(idx + 1).ok_or("Missing value for --config")?;
+            SafetyPolicy::from_file(config_path).map_err(|err| err.to_string())?
         }
+        None => SafetyPolicy::default(),
+    };
+
+    let mut parsed = Options {
+        mode: ParseMode::Strict,
+        tolerance: 1,
+        report: false,
+        parallel: false,
+        repair: false,
+        summary: false,
+        json: false,
+    };
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let key = match option.as_str() {
+            "--config" => {
+                options.next();
+                continue;
+            }
+            "--summary" => {
+                parsed.summary = true;
+                continue;
+            }
+            "--format" => {
+                parsed.json = match options.next().map(String::as_str) {
+                    Some("text") => false,
+                    Some("json") => true,
+                    value => return Err(format!("Invalid format: {}", value.unwrap_or(""))),
+                };
+                continue;
+            }
+            "--lenient" => {
+                parsed.mode = ParseMode::Lenient;
+                continue;
+            }
+            "--repair" => {
+                parsed.repair = true;
+                continue;
+            }
+            "--parallel" => {
+                parsed.parallel = true;
+                continue;
+            }
+            "--report" => {
+                parsed.report = true;
+                continue;
+            }
+            "--allow-plateaus" => {
+                policy.allow_plateaus = true;
+                continue;
+            }
+            "--tolerance" => {
+                let value = options
+                    .next()
+                    .ok_or_else(|| format!("Missing value for {}", option))?;
+                parsed.tolerance = value
+                    .parse()
+                    .map_err(|_| format!("Invalid tolerance: {}", value))?;
+                continue;
+            }
+            "--min-step" => "min_step",
+            "--max-step" => "max_step",
+            "--direction" => "direction",
+            _ => return Err(format!("Unknown option: {}", option)),
+        };
+        let value = options
+            .next()
+            .ok_or_else(|| format!("Missing value for {}", option))?;
+        policy.set(key, value)?;
     }
 
-    #[test]
-    fn test_is_safe_report_problem_dampener_can_tolerate_unsafe_level() {
-        let unsafe_reports = vec![
-            vec![24, 21, 22, 24, 25, 26, 27],
-        ];
-
-        for report in unsafe_reports {
-            assert_eq!(is_safe_report_problem_dampener(&report), true, "Report {:?} considered unsafe, but expected to be safe", report);
-        }
-    }
+    policy.validate()?;
+    Ok((policy, parsed))
 }
@@ -0,0 +1,129 @@
+use std::fmt;
+use std::io;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseMode {
+    // Abort on the first line with an invalid level.
+    Strict,
+    // Skip lines with invalid levels and collect them instead.
+    Lenient,
+}
+
+// A token that is not a valid level. Columns are 1-based and count characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidLevel {
+    pub columns: Range<usize>,
+    pub token: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedLine {
+    pub line: usize,
+    pub errors: Vec<InvalidLevel>,
+}
+
+impl fmt::Display for RejectedLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let errors: Vec<String> = self
+            .errors
+            .iter()
+            .map(|error| match error.columns.len() {
+                1 => format!(
+                    "column {}: invalid level '{}'",
+                    error.columns.start, error.token
+                ),
+                _ => format!(
+                    "columns {}-{}: invalid level '{}'",
+                    error.columns.start,
+                    error.columns.end - 1,
+                    error.token
+                ),
+            })
+            .collect();
+        write!(f, "line {}: {}", self.line, errors.join(", "))
+    }
+}
+
+impl From<RejectedLine> for io::Error {
+    fn from(rejected: RejectedLine) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, rejected.to_string())
+    }
+}
+
+// Parses a line into `levels`, reporting every token that is not a level.
+pub fn parse_levels(line: &str, levels: &mut Vec<i32>) -> Result<(), Vec<InvalidLevel>> {
+    levels.clear();
+    let mut errors = vec![];
+    let mut token_start = None;
+
+    for (column, (idx, ch)) in line.char_indices().chain([(line.len(), ' ')]).enumerate() {
+        match (ch.is_whitespace(), token_start) {
+            (false, None) => token_start = Some((column, idx)),
+            (true, Some((start_column, start_idx))) => {
+                let token = &line[start_idx..idx];
+                match token.parse::<i32>() {
+                    Ok(level) => levels.push(level),
+                    Err(_) => errors.push(InvalidLevel {
+                        columns: start_column + 1..column + 1,
+                        token: token.to_string(),
+                    }),
+                }
+                token_start = None;
+            }
+            _ => {}
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_levels_collects_every_invalid_token() {
+        let mut levels = vec![];
+        let errors = parse_levels("1 x2  3 99999999999 é", &mut levels).unwrap_err();
+
+        assert_eq!(
+            errors,
+            vec![
+                InvalidLevel {
+                    columns: 3..5,
+                    token: "x2".to_string(),
+                },
+                InvalidLevel {
+                    columns: 9..20,
+                    token: "99999999999".to_string(),
+                },
+                InvalidLevel {
+                    columns: 21..22,
+                    token: "é".to_string(),
+                },
+            ]
+        );
+        assert_eq!(levels, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_rejected_line_display() {
+        let rejected = RejectedLine {
+            line: 4,
+            errors: vec![InvalidLevel {
+                columns: 3..5,
+                token: "x2".to_string(),
+            }],
+        };
+
+        assert_eq!(
+            rejected.to_string(),
+            "line 4: columns 3-4: invalid level 'x2'"
+        );
+    }
+}
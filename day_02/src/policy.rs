@@ -0,0 +1,202 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Increasing,
+    Decreasing,
+    Either,
+}
+
+impl FromStr for Direction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "increasing" => Ok(Direction::Increasing),
+            "decreasing" => Ok(Direction::Decreasing),
+            "either" => Ok(Direction::Either),
+            _ => Err(format!("Invalid direction: {}", s)),
+        }
+    }
+}
+
+// Rules a report has to follow to be considered safe. The default policy is
+// the one from the puzzle: levels change by 1 to 3 at every step and keep
+// moving in the same direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SafetyPolicy {
+    pub min_step: i32,
+    pub max_step: i32,
+    pub allow_plateaus: bool,
+    pub direction: Direction,
+}
+
+impl Default for SafetyPolicy {
+    fn default() -> Self {
+        SafetyPolicy {
+            min_step: 1,
+            max_step: 3,
+            allow_plateaus: false,
+            direction: Direction::Either,
+        }
+    }
+}
+
+impl SafetyPolicy {
+    // Reads a config file with one `key = value` setting per line. Empty
+    // lines and lines starting with `#` are ignored, missing keys keep their
+    // default value.
+    pub fn from_file(file_path: &str) -> io::Result<SafetyPolicy> {
+        let content = fs::read_to_string(Path::new(file_path))?;
+        let mut policy = SafetyPolicy::default();
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line.split_once('=').ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid config line: {}", line),
+                )
+            })?;
+            policy
+                .set(key.trim(), value.trim())
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        }
+
+        policy
+            .validate()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        Ok(policy)
+    }
+
+    // Updates a single setting, using the same names as the config file.
+    // Steps are only compared with each other by `validate`, so that they can
+    // be set in any order.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "min_step" => self.min_step = parse_step(key, value)?,
+            "max_step" => self.max_step = parse_step(key, value)?,
+            "allow_plateaus" => self.allow_plateaus = parse_value(key, value)?,
+            "direction" => self.direction = value.parse()?,
+            _ => return Err(format!("Unknown setting: {}", key)),
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.min_step < 0 || self.max_step < 0 {
+            return Err(format!(
+                "Steps must not be negative: min_step = {}, max_step = {}",
+                self.min_step, self.max_step
+            ));
+        }
+        if self.min_step > self.max_step {
+            return Err(format!(
+                "min_step ({}) is larger than max_step ({})",
+                self.min_step, self.max_step
+            ));
+        }
+        Ok(())
+    }
+
+    // Checks a single step between two neighbouring levels, ignoring direction.
+    pub fn is_valid_step(&self, prev: i32, current: i32) -> bool {
+        let step = current.abs_diff(prev);
+        if step == 0 {
+            return self.allow_plateaus;
+        }
+        step >= self.min_step as u32 && step <= self.max_step as u32
+    }
+
+    // Checks a single step for a report that is known to be growing or not.
+    pub fn is_valid_directed_step(&self, prev: i32, current: i32, growing: bool) -> bool {
+        self.is_valid_step(prev, current) && (prev == current || (prev < current) == growing)
+    }
+
+    // Directions a report may follow, as values of `growing`.
+    pub fn allowed_growth(&self) -> &'static [bool] {
+        match self.direction {
+            Direction::Increasing => &[true],
+            Direction::Decreasing => &[false],
+            Direction::Either => &[true, false],
+        }
+    }
+}
+
+fn parse_step(key: &str, value: &str) -> Result<i32, String> {
+    let step = parse_value(key, value)?;
+    if step < 0 {
+        return Err(format!("Invalid value for {}: {} is negative", key, value));
+    }
+    Ok(step)
+}
+
+fn parse_value<T: FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for {}: {}", key, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_policy_values() {
+        let mut policy = SafetyPolicy::default();
+        policy.set("min_step", "2").unwrap();
+        policy.set("allow_plateaus", "true").unwrap();
+        policy.set("direction", "decreasing").unwrap();
+
+        assert_eq!(
+            policy,
+            SafetyPolicy {
+                min_step: 2,
+                max_step: 3,
+                allow_plateaus: true,
+                direction: Direction::Decreasing,
+            }
+        );
+        assert!(policy.set("max_step", "many").is_err());
+        assert!(policy.set("unknown", "1").is_err());
+        assert!(policy.set("min_step", "-1").is_err());
+    }
+
+    #[test]
+    fn test_validate_policy() {
+        let mut policy = SafetyPolicy::default();
+        policy.set("min_step", "5").unwrap();
+        assert!(policy.validate().is_err());
+        policy.set("max_step", "6").unwrap();
+        assert_eq!(policy.validate(), Ok(()));
+
+        let negative = SafetyPolicy {
+            min_step: -3,
+            ..SafetyPolicy::default()
+        };
+        assert!(negative.validate().is_err());
+    }
+
+    #[test]
+    fn test_is_valid_step() {
+        let policy = SafetyPolicy::default();
+        assert!(policy.is_valid_step(1, 4));
+        assert!(policy.is_valid_step(4, 1));
+        assert!(!policy.is_valid_step(1, 1));
+        assert!(!policy.is_valid_step(1, 5));
+
+        let plateaus = SafetyPolicy {
+            allow_plateaus: true,
+            ..SafetyPolicy::default()
+        };
+        assert!(plateaus.is_valid_step(1, 1));
+        assert!(!policy.is_valid_step(i32::MIN, i32::MAX));
+        assert!(!policy.is_valid_step(i32::MAX, i32::MIN));
+    }
+}
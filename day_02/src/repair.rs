@@ -0,0 +1,332 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::{find_unsafe_level, SafetyPolicy};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepairStrategy {
+    // Replace as few levels as possible.
+    FewestChanges,
+    // Keep the sum of absolute changes to the levels as small as possible.
+    SmallestAdjustment,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repair {
+    pub strategy: RepairStrategy,
+    pub changed: Vec<usize>,
+    pub total_adjustment: i64,
+    pub report: Vec<i32>,
+}
+
+impl Repair {
+    fn new(strategy: RepairStrategy, original: &[i32], levels: Vec<i64>) -> Option<Repair> {
+        let report = levels
+            .into_iter()
+            .map(i32::try_from)
+            .collect::<Result<Vec<i32>, _>>()
+            .ok()?;
+        let changed = (0..report.len())
+            .filter(|&idx| report[idx] != original[idx])
+            .collect();
+        let total_adjustment = report
+            .iter()
+            .zip(original.iter())
+            .map(|(&new, &old)| (new as i64 - old as i64).abs())
+            .sum();
+        Some(Repair {
+            strategy,
+            changed,
+            total_adjustment,
+            report,
+        })
+    }
+}
+
+// Suggests ways to make an unsafe report safe by replacing levels instead of
+// removing them. Options are ranked by the number of changed levels and then
+// by the total adjustment. Safe reports need no repair and get no options.
+pub fn suggest_repairs(report: &[i32], policy: &SafetyPolicy) -> Vec<Repair> {
+    if find_unsafe_level(report, policy).is_none() {
+        return vec![];
+    }
+    let steps = match StepRange::new(policy) {
+        Some(steps) => steps,
+        None => return vec![],
+    };
+
+    let mut repairs: Vec<Repair> = vec![];
+    for &growing in policy.allowed_growth() {
+        let sign = if growing { 1 } else { -1 };
+        // Both searches work on increasing levels, decreasing reports are
+        // mirrored before and after.
+        let levels: Vec<i64> = report.iter().map(|&level| sign * level as i64).collect();
+
+        let fewest = fewest_changes(&levels, &steps);
+        let smallest = smallest_adjustment(&levels, &steps);
+        for (strategy, repaired) in [
+            (RepairStrategy::FewestChanges, fewest),
+            (RepairStrategy::SmallestAdjustment, smallest),
+        ] {
+            let repaired = repaired.into_iter().map(|level| sign * level).collect();
+            if let Some(repair) = Repair::new(strategy, report, repaired) {
+                if find_unsafe_level(&repair.report, policy).is_none()
+                    && !repairs.iter().any(|other| other.report == repair.report)
+                {
+                    repairs.push(repair);
+                }
+            }
+        }
+    }
+
+    repairs.sort_by_key(|repair| (repair.changed.len(), repair.total_adjustment));
+    repairs
+}
+
+// Allowed size of a single increasing step.
+struct StepRange {
+    min: i64,
+    max: i64,
+    plateaus: bool,
+}
+
+impl StepRange {
+    fn new(policy: &SafetyPolicy) -> Option<StepRange> {
+        let min = policy.min_step.max(1) as i64;
+        let max = policy.max_step as i64;
+        if min > max {
+            return None;
+        }
+        Some(StepRange {
+            min,
+            max,
+            plateaus: policy.allow_plateaus,
+        })
+    }
+
+    // Number of non-plateau steps needed to climb `rise` in `steps` steps, if
+    // that is possible at all.
+    fn moving_steps(&self, rise: i64, steps: i64) -> Option<i64> {
+        if !self.plateaus {
+            return (steps * self.min <= rise && rise <= steps * self.max).then_some(steps);
+        }
+        if rise == 0 {
+            return Some(0);
+        }
+        let moving = (rise + self.max - 1) / self.max;
+        (rise > 0 && moving <= steps && moving * self.min <= rise).then_some(moving)
+    }
+
+    // Step sizes that climb exactly `rise` in `steps` steps.
+    fn fill(&self, rise: i64, steps: i64) -> Vec<i64> {
+        let moving = self.moving_steps(rise, steps).unwrap();
+        let mut extra = rise - moving * self.min;
+        (0..steps)
+            .map(|step| {
+                if step >= moving {
+                    return 0;
+                }
+                let bonus = extra.min(self.max - self.min);
+                extra -= bonus;
+                self.min + bonus
+            })
+            .collect()
+    }
+}
+
+// Keeps the longest subsequence of levels whose gaps can be bridged with
+// valid steps and rewrites everything else.
+fn fewest_changes(levels: &[i64], steps: &StepRange) -> Vec<i64> {
+    let mut kept = vec![1; levels.len()];
+    let mut previous: Vec<Option<usize>> = vec![None; levels.len()];
+
+    for current in 1..levels.len() {
+        for prev in 0..current {
+            if kept[prev] + 1 > kept[current]
+                && steps
+                    .moving_steps(levels[current] - levels[prev], (current - prev) as i64)
+                    .is_some()
+            {
+                kept[current] = kept[prev] + 1;
+                previous[current] = Some(prev);
+            }
+        }
+    }
+
+    let last = match (0..levels.len()).max_by_key(|&idx| kept[idx]) {
+        Some(last) => last,
+        None => return vec![],
+    };
+
+    let mut repaired = levels.to_vec();
+    let mut current = last;
+    while let Some(prev) = previous[current] {
+        let rise = levels[current] - levels[prev];
+        let mut level = levels[prev];
+        for (offset, step) in steps
+            .fill(rise, (current - prev) as i64)
+            .into_iter()
+            .enumerate()
+        {
+            level += step;
+            repaired[prev + offset + 1] = level;
+        }
+        current = prev;
+    }
+
+    for idx in (0..current).rev() {
+        repaired[idx] = repaired[idx + 1] - steps.min;
+    }
+    for idx in last + 1..levels.len() {
+        repaired[idx] = repaired[idx - 1] + steps.min;
+    }
+    repaired
+}
+
+// Minimises the total adjustment with the slope trick: f_i(x) is the cheapest
+// cost of the first i levels with level i set to x. It stays convex and
+// piecewise linear, so it is stored as the breakpoints left and right of its
+// minimum. Allowing a step of [lo, max] shifts the left breakpoints by lo and
+// the right ones by max.
+fn smallest_adjustment(levels: &[i64], steps: &StepRange) -> Vec<i64> {
+    // Plateaus only keep the step range convex when they border on it.
+    let lo = if steps.plateaus && steps.min == 1 {
+        0
+    } else {
+        steps.min
+    };
+
+    let mut left: BinaryHeap<i64> = BinaryHeap::new();
+    let mut right: BinaryHeap<Reverse<i64>> = BinaryHeap::new();
+    let (mut shift_left, mut shift_right) = (0, 0);
+    let mut minimums = Vec::with_capacity(levels.len());
+
+    for (idx, &level) in levels.iter().enumerate() {
+        if idx > 0 {
+            shift_left += lo;
+            shift_right += steps.max;
+        }
+
+        // Add max(0, x - level)
+        left.push(level - shift_left);
+        let moved = left.pop().unwrap() + shift_left;
+        right.push(Reverse(moved - shift_right));
+
+        // Add max(0, level - x)
+        right.push(Reverse(level - shift_right));
+        let Reverse(moved) = right.pop().unwrap();
+        left.push(moved + shift_right - shift_left);
+
+        let low = left.peek().unwrap() + shift_left;
+        let high = right.peek().unwrap().0 + shift_right;
+        minimums.push((low, high));
+    }
+
+    let mut repaired = vec![0; levels.len()];
+    for idx in (0..levels.len()).rev() {
+        let (low, high) = minimums[idx];
+        repaired[idx] = if idx + 1 == levels.len() {
+            low
+        } else {
+            // Closest minimum of f_idx that still reaches the next level.
+            let (from, to) = (repaired[idx + 1] - steps.max, repaired[idx + 1] - lo);
+            if to < low {
+                to
+            } else if from > high {
+                from
+            } else {
+                from.max(low)
+            }
+        };
+    }
+    repaired
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Direction;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_suggest_repairs_for_safe_report() {
+        assert!(suggest_repairs(&[1, 2, 3], &SafetyPolicy::default()).is_empty());
+    }
+
+    #[test]
+    fn test_suggest_repairs_ranks_fewest_changes_first() {
+        let repairs = suggest_repairs(&[1, 2, 7, 8, 9], &SafetyPolicy::default());
+
+        assert_eq!(repairs[0].changed.len(), 1);
+        assert_eq!(repairs[0].changed, vec![2]);
+        assert_eq!(repairs[0].total_adjustment, 2);
+        assert_eq!(repairs[0].report, vec![1, 2, 5, 8, 9]);
+        for repair in repairs {
+            assert!(find_unsafe_level(&repair.report, &SafetyPolicy::default()).is_none());
+        }
+    }
+
+    #[test]
+    fn test_smallest_adjustment() {
+        let steps = StepRange::new(&SafetyPolicy::default()).unwrap();
+
+        // Moving 7 down by 2 costs less than moving 1 and 2 up.
+        assert_eq!(
+            smallest_adjustment(&[1, 2, 7, 8, 9], &steps),
+            vec![1, 2, 5, 8, 9]
+        );
+        assert_eq!(smallest_adjustment(&[5, 5, 5], &steps), vec![4, 5, 6]);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_smallest_adjustment_matches_brute_force(report in prop::collection::vec(0..8i32, 5)) {
+            let policy = SafetyPolicy {
+                direction: Direction::Increasing,
+                ..SafetyPolicy::default()
+            };
+            let steps = StepRange::new(&policy).unwrap();
+
+            // Every increasing report starting close enough to the input
+            let mut best = i64::MAX;
+            for start in -15..25 {
+                for mask in 0..3i64.pow(4) {
+                    let mut level = start;
+                    let mut cost = (level - report[0] as i64).abs();
+                    let mut rest = mask;
+                    for &original in &report[1..] {
+                        level += rest % 3 + 1;
+                        rest /= 3;
+                        cost += (level - original as i64).abs();
+                    }
+                    best = best.min(cost);
+                }
+            }
+
+            let levels: Vec<i64> = report.iter().map(|&level| level as i64).collect();
+            let repaired = smallest_adjustment(&levels, &steps);
+            let repair = Repair::new(RepairStrategy::SmallestAdjustment, &report, repaired).unwrap();
+            prop_assert!(find_unsafe_level(&repair.report, &policy).is_none());
+            prop_assert_eq!(repair.total_adjustment, best);
+        }
+
+        #[test]
+        fn prop_repairs_are_safe(
+            report in prop::collection::vec(0..20i32, 2..10),
+            allow_plateaus in any::<bool>(),
+            min_step in 1..3i32,
+        ) {
+            let policy = SafetyPolicy {
+                min_step,
+                allow_plateaus,
+                ..SafetyPolicy::default()
+            };
+            let repairs = suggest_repairs(&report, &policy);
+            prop_assert_eq!(repairs.is_empty(), find_unsafe_level(&report, &policy).is_none());
+            for repair in repairs {
+                prop_assert_eq!(repair.report.len(), report.len());
+                prop_assert!(find_unsafe_level(&repair.report, &policy).is_none());
+            }
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use std::io::{self, BufRead};
+
+use rayon::prelude::*;
+
+use crate::parse::{parse_levels, ParseMode, RejectedLine};
+use crate::{is_safe_report, is_safe_with_tolerance, SafetyPolicy};
+
+// Answers for both parts, collected while the input is read. In lenient mode
+// lines with invalid levels are skipped and listed in `rejected`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReportCounts {
+    pub reports: usize,
+    pub safe: usize,
+    pub tolerated: usize,
+    pub rejected: Vec<RejectedLine>,
+}
+
+impl ReportCounts {
+    fn add(&mut self, report: &[i32], policy: &SafetyPolicy, tolerance: usize) {
+        self.reports += 1;
+        if is_safe_report(report, policy) {
+            self.safe += 1;
+            self.tolerated += 1;
+        } else if is_safe_with_tolerance(report, policy, tolerance) {
+            self.tolerated += 1;
+        }
+    }
+
+    fn merge(mut self, other: ReportCounts) -> ReportCounts {
+        self.reports += other.reports;
+        self.safe += other.safe;
+        self.tolerated += other.tolerated;
+        self.rejected.extend(other.rejected);
+        self
+    }
+}
+
+// Calls `f` for every non-empty report, reusing the same buffers for every
+// line so memory use does not depend on the size of the input. Returns the
+// lines that were skipped in lenient mode.
+pub fn for_each_report<R: BufRead, F: FnMut(&[i32])>(
+    mut reader: R,
+    mode: ParseMode,
+    mut f: F,
+) -> io::Result<Vec<RejectedLine>> {
+    let mut rejected = vec![];
+    let mut line = String::new();
+    let mut levels = Vec::new();
+    let mut line_number = 0;
+    while reader.read_line(&mut line)? > 0 {
+        line_number += 1;
+        match parse_levels(&line, &mut levels) {
+            Ok(()) if !levels.is_empty() => f(&levels),
+            Ok(()) => {}
+            Err(errors) => {
+                let rejected_line = RejectedLine {
+                    line: line_number,
+                    errors,
+                };
+                match mode {
+                    ParseMode::Strict => return Err(rejected_line.into()),
+                    ParseMode::Lenient => rejected.push(rejected_line),
+                }
+            }
+        }
+        line.clear();
+    }
+    Ok(rejected)
+}
+
+pub fn count_reports<R: BufRead>(
+    reader: R,
+    mode: ParseMode,
+    policy: &SafetyPolicy,
+    tolerance: usize,
+) -> io::Result<ReportCounts> {
+    let mut counts = ReportCounts::default();
+    counts.rejected =
+        for_each_report(reader, mode, |report| counts.add(report, policy, tolerance))?;
+    Ok(counts)
+}
+
+// Reads the input in chunks of `chunk_lines` lines and classifies each chunk
+// in parallel. Only one chunk is held in memory at a time.
+pub fn count_reports_parallel<R: BufRead>(
+    reader: R,
+    mode: ParseMode,
+    policy: &SafetyPolicy,
+    tolerance: usize,
+    chunk_lines: usize,
+) -> io::Result<ReportCounts> {
+    let mut counts = ReportCounts::default();
+    let mut lines = reader.lines();
+    let mut chunk: Vec<String> = Vec::with_capacity(chunk_lines);
+    let mut first_line = 1;
+
+    loop {
+        chunk.clear();
+        for line in lines.by_ref().take(chunk_lines.max(1)) {
+            chunk.push(line?);
+        }
+        if chunk.is_empty() {
+            return Ok(counts);
+        }
+
+        let chunk_counts = chunk
+            .par_iter()
+            .enumerate()
+            .map_init(
+                Vec::new,
+                |levels, (offset, line)| -> io::Result<ReportCounts> {
+                    let mut counts = ReportCounts::default();
+                    match parse_levels(line, levels) {
+                        Ok(()) if !levels.is_empty() => counts.add(levels, policy, tolerance),
+                        Ok(()) => {}
+                        Err(errors) => {
+                            let rejected_line = RejectedLine {
+                                line: first_line + offset,
+                                errors,
+                            };
+                            match mode {
+                                ParseMode::Strict => return Err(rejected_line.into()),
+                                ParseMode::Lenient => counts.rejected.push(rejected_line),
+                            }
+                        }
+                    }
+                    Ok(counts)
+                },
+            )
+            .try_reduce(ReportCounts::default, |a, b| Ok(a.merge(b)))?;
+        counts = counts.merge(chunk_counts);
+        first_line += chunk.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::InvalidLevel;
+
+    const REPORTS: &str = "7 6 4 2 1\n1 2 7 8 9\n9 7 6 2 1\n1 3 2 4 5\n8 6 4 4 1\n1 3 6 7 9\n\n";
+
+    #[test]
+    fn test_count_reports() {
+        let policy = SafetyPolicy::default();
+        let expected = ReportCounts {
+            reports: 6,
+            safe: 2,
+            tolerated: 4,
+            rejected: vec![],
+        };
+
+        assert_eq!(
+            count_reports(REPORTS.as_bytes(), ParseMode::Strict, &policy, 1).unwrap(),
+            expected
+        );
+        for chunk_lines in [1, 2, 4, 100] {
+            assert_eq!(
+                count_reports_parallel(
+                    REPORTS.as_bytes(),
+                    ParseMode::Strict,
+                    &policy,
+                    1,
+                    chunk_lines
+                )
+                .unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_count_reports_rejects_invalid_level() {
+        let policy = SafetyPolicy::default();
+        let input = "1 2 3\n1 2 x\n".as_bytes();
+
+        let err = count_reports(input, ParseMode::Strict, &policy, 1).unwrap_err();
+        assert_eq!(err.to_string(), "line 2: column 5: invalid level 'x'");
+        assert!(count_reports_parallel(input, ParseMode::Strict, &policy, 1, 8).is_err());
+    }
+
+    #[test]
+    fn test_count_reports_lenient() {
+        let policy = SafetyPolicy::default();
+        let input = "1 2 3\n1 2 x\n\n4 y 5 z\n7 6 5\n";
+        let expected = ReportCounts {
+            reports: 2,
+            safe: 2,
+            tolerated: 2,
+            rejected: vec![
+                RejectedLine {
+                    line: 2,
+                    errors: vec![InvalidLevel {
+                        columns: 5..6,
+                        token: "x".to_string(),
+                    }],
+                },
+                RejectedLine {
+                    line: 4,
+                    errors: vec![
+                        InvalidLevel {
+                            columns: 3..4,
+                            token: "y".to_string(),
+                        },
+                        InvalidLevel {
+                            columns: 7..8,
+                            token: "z".to_string(),
+                        },
+                    ],
+                },
+            ],
+        };
+
+        assert_eq!(
+            count_reports(input.as_bytes(), ParseMode::Lenient, &policy, 1).unwrap(),
+            expected
+        );
+        for chunk_lines in [1, 2, 3] {
+            assert_eq!(
+                count_reports_parallel(
+                    input.as_bytes(),
+                    ParseMode::Lenient,
+                    &policy,
+                    1,
+                    chunk_lines
+                )
+                .unwrap(),
+                expected
+            );
+        }
+    }
+}
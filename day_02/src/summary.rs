@@ -0,0 +1,306 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::{is_safe_with_tolerance, RejectedLine, SafetyPolicy, ViolationKind, Violations};
+
+// Aggregate diagnostics over a whole report file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReportSummary {
+    pub reports: usize,
+    pub safe: usize,
+    // Unsafe reports that the problem dampener makes safe.
+    pub rescued: usize,
+    pub increasing: usize,
+    pub decreasing: usize,
+    // Reports without a single step that is not a plateau.
+    pub flat: usize,
+    // Absolute step size to the number of steps of that size.
+    pub step_sizes: BTreeMap<u32, usize>,
+    // Rule broken by the first unsafe step of each unsafe report.
+    pub failures_by_rule: BTreeMap<&'static str, usize>,
+    // Index of the first unsafe step to the number of reports failing there.
+    pub failure_positions: BTreeMap<usize, usize>,
+    // Lines skipped in lenient mode.
+    pub rejected: Vec<RejectedLine>,
+}
+
+impl ReportSummary {
+    pub fn add(&mut self, report: &[i32], policy: &SafetyPolicy, tolerance: usize) {
+        self.reports += 1;
+
+        for step in report.windows(2) {
+            *self
+                .step_sizes
+                .entry(step[1].abs_diff(step[0]))
+                .or_default() += 1;
+        }
+
+        match report.windows(2).find(|step| step[0] != step[1]) {
+            Some(step) if step[0] < step[1] => self.increasing += 1,
+            Some(_) => self.decreasing += 1,
+            None => self.flat += 1,
+        }
+
+        match Violations::new(report, policy).next() {
+            None => self.safe += 1,
+            Some(violation) => {
+                *self
+                    .failures_by_rule
+                    .entry(rule_name(violation.kind))
+                    .or_default() += 1;
+                *self
+                    .failure_positions
+                    .entry(violation.indices.0)
+                    .or_default() += 1;
+                if is_safe_with_tolerance(report, policy, tolerance) {
+                    self.rescued += 1;
+                }
+            }
+        }
+    }
+
+    pub fn unsafe_reports(&self) -> usize {
+        self.reports - self.safe
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        let share = |count: usize| {
+            if self.reports == 0 {
+                0.0
+            } else {
+                100.0 * count as f64 / self.reports as f64
+            }
+        };
+
+        writeln!(text, "Reports: {}", self.reports).unwrap();
+        writeln!(text, "Safe: {} ({:.1}%)", self.safe, share(self.safe)).unwrap();
+        writeln!(
+            text,
+            "Unsafe: {} ({:.1}%)",
+            self.unsafe_reports(),
+            share(self.unsafe_reports())
+        )
+        .unwrap();
+        writeln!(text, "Rescued by dampener: {}", self.rescued).unwrap();
+        writeln!(
+            text,
+            "Direction: {} increasing ({:.1}%), {} decreasing ({:.1}%), {} flat",
+            self.increasing,
+            share(self.increasing),
+            self.decreasing,
+            share(self.decreasing),
+            self.flat
+        )
+        .unwrap();
+
+        writeln!(text, "Step sizes:").unwrap();
+        for (size, count) in &self.step_sizes {
+            writeln!(text, "  {:>4}: {}", size, count).unwrap();
+        }
+
+        writeln!(text, "Failures by rule:").unwrap();
+        let mut rules: Vec<(&&str, &usize)> = self.failures_by_rule.iter().collect();
+        rules.sort_by(|a, b| b.1.cmp(a.1));
+        for (rule, count) in rules {
+            writeln!(text, "  {}: {}", rule, count).unwrap();
+        }
+
+        writeln!(text, "First failure at step:").unwrap();
+        for (position, count) in &self.failure_positions {
+            writeln!(text, "  {:>4}: {}", position, count).unwrap();
+        }
+
+        if !self.rejected.is_empty() {
+            writeln!(text, "Rejected lines: {}", self.rejected.len()).unwrap();
+            for rejected_line in &self.rejected {
+                writeln!(text, "  {}", rejected_line).unwrap();
+            }
+        }
+
+        text
+    }
+
+    pub fn to_json(&self) -> String {
+        let object = |entries: Vec<(String, usize)>| {
+            let fields: Vec<String> = entries
+                .into_iter()
+                .map(|(key, value)| format!("\"{}\": {}", key, value))
+                .collect();
+            format!("{{{}}}", fields.join(", "))
+        };
+
+        let rejected: Vec<String> = self
+            .rejected
+            .iter()
+            .map(|rejected_line| {
+                let errors: Vec<String> = rejected_line
+                    .errors
+                    .iter()
+                    .map(|error| {
+                        format!(
+                            "{{\"columns\": [{}, {}], \"token\": {}}}",
+                            error.columns.start,
+                            error.columns.end - 1,
+                            json_string(&error.token)
+                        )
+                    })
+                    .collect();
+                format!(
+                    "{{\"line\": {}, \"errors\": [{}]}}",
+                    rejected_line.line,
+                    errors.join(", ")
+                )
+            })
+            .collect();
+
+        let fields = [
+            ("reports", self.reports.to_string()),
+            ("safe", self.safe.to_string()),
+            ("unsafe", self.unsafe_reports().to_string()),
+            ("rescued", self.rescued.to_string()),
+            ("increasing", self.increasing.to_string()),
+            ("decreasing", self.decreasing.to_string()),
+            ("flat", self.flat.to_string()),
+            (
+                "step_sizes",
+                object(
+                    self.step_sizes
+                        .iter()
+                        .map(|(size, &count)| (size.to_string(), count))
+                        .collect(),
+                ),
+            ),
+            (
+                "failures_by_rule",
+                object(
+                    self.failures_by_rule
+                        .iter()
+                        .map(|(rule, &count)| (rule.to_string(), count))
+                        .collect(),
+                ),
+            ),
+            (
+                "failure_positions",
+                object(
+                    self.failure_positions
+                        .iter()
+                        .map(|(position, &count)| (position.to_string(), count))
+                        .collect(),
+                ),
+            ),
+            ("rejected", format!("[{}]", rejected.join(", "))),
+        ];
+
+        let fields: Vec<String> = fields
+            .iter()
+            .map(|(key, value)| format!("  \"{}\": {}", key, value))
+            .collect();
+        format!("{{\n{}\n}}", fields.join(",\n"))
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut escaped = String::from("\"");
+    for ch in text.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            ch if ch.is_control() => write!(escaped, "\\u{:04x}", ch as u32).unwrap(),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn rule_name(kind: ViolationKind) -> &'static str {
+    match kind {
+        ViolationKind::TooSmall => "too_small",
+        ViolationKind::TooLarge => "too_large",
+        ViolationKind::DirectionChange => "direction_change",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InvalidLevel;
+
+    fn example_summary() -> ReportSummary {
+        let policy = SafetyPolicy::default();
+        let mut summary = ReportSummary::default();
+        for report in [
+            vec![7, 6, 4, 2, 1],
+            vec![1, 2, 7, 8, 9],
+            vec![9, 7, 6, 2, 1],
+            vec![1, 3, 2, 4, 5],
+            vec![8, 6, 4, 4, 1],
+            vec![1, 3, 6, 7, 9],
+        ] {
+            summary.add(&report, &policy, 1);
+        }
+        summary
+    }
+
+    #[test]
+    fn test_summary_counts() {
+        let summary = example_summary();
+
+        assert_eq!(summary.reports, 6);
+        assert_eq!(summary.safe, 2);
+        assert_eq!(summary.rescued, 2);
+        assert_eq!(
+            (summary.increasing, summary.decreasing, summary.flat),
+            (3, 3, 0)
+        );
+        assert_eq!(
+            summary.failures_by_rule,
+            BTreeMap::from([("too_large", 2), ("direction_change", 1), ("too_small", 1)])
+        );
+        assert_eq!(summary.failure_positions, BTreeMap::from([(1, 2), (2, 2)]));
+        assert_eq!(summary.step_sizes.values().sum::<usize>(), 24);
+        assert_eq!(summary.step_sizes[&0], 1);
+    }
+
+    #[test]
+    fn test_summary_of_extreme_levels() {
+        let mut summary = ReportSummary::default();
+        summary.add(&[i32::MIN, i32::MAX], &SafetyPolicy::default(), 1);
+
+        assert_eq!(summary.step_sizes, BTreeMap::from([(u32::MAX, 1)]));
+        assert_eq!(summary.failures_by_rule, BTreeMap::from([("too_large", 1)]));
+        assert_eq!(summary.rescued, 1);
+    }
+
+    #[test]
+    fn test_summary_json() {
+        let json = example_summary().to_json();
+
+        assert!(json.starts_with("{\n  \"reports\": 6,\n  \"safe\": 2,"));
+        assert!(json.contains(
+            "\"failures_by_rule\": {\"direction_change\": 1, \"too_large\": 2, \"too_small\": 1}"
+        ));
+        assert!(json.contains("\"failure_positions\": {\"1\": 2, \"2\": 2}"));
+        assert!(json.ends_with("\"rejected\": []\n}"));
+    }
+
+    #[test]
+    fn test_summary_json_lists_rejected_lines() {
+        let mut summary = example_summary();
+        summary.rejected.push(RejectedLine {
+            line: 7,
+            errors: vec![InvalidLevel {
+                columns: 3..6,
+                token: "x\"\\".to_string(),
+            }],
+        });
+
+        assert!(summary.to_json().ends_with(
+            "\"rejected\": [{\"line\": 7, \"errors\": [{\"columns\": [3, 5], \"token\": \"x\\\"\\\\\"}]}]\n}"
+        ));
+        assert!(summary
+            .to_text()
+            .ends_with("Rejected lines: 1\n  line 7: columns 3-5: invalid level 'x\"\\'\n"));
+    }
+}
@@ -0,0 +1,293 @@
+use std::fmt;
+
+use crate::{find_removable_level, is_safe_with_removals, Direction, SafetyPolicy};
+
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViolationKind {
+    TooSmall,
+    TooLarge,
+    DirectionChange,
+}
+
+impl fmt::Display for ViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ViolationKind::TooSmall => write!(f, "step too small"),
+            ViolationKind::TooLarge => write!(f, "step too large"),
+            ViolationKind::DirectionChange => write!(f, "direction changed"),
+        }
+    }
+}
+
+// A single step between two neighbouring levels that breaks the policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Violation {
+    pub kind: ViolationKind,
+    pub indices: (usize, usize),
+    pub values: (i32, i32),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} between levels {} and {} ({} -> {})",
+            self.kind, self.indices.0, self.indices.1, self.values.0, self.values.1
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SafetyVerdict {
+    Safe,
+    // Sorted indices of the levels the problem dampener removes.
+    SafeAfterRemoval { indices: Vec<usize> },
+    Unsafe { violations: Vec<Violation> },
+}
+
+// Iterates over every step of a report that breaks the policy. With
+// `Direction::Either` the first step that is not a plateau decides the
+// direction of the whole report.
+pub struct Violations<'a> {
+    report: &'a [i32],
+    policy: &'a SafetyPolicy,
+    current_idx: usize,
+    growing: Option<bool>,
+}
+
+impl<'a> Violations<'a> {
+    pub fn new(report: &'a [i32], policy: &'a SafetyPolicy) -> Violations<'a> {
+        let growing = match policy.direction {
+            Direction::Increasing => Some(true),
+            Direction::Decreasing => Some(false),
+            Direction::Either => None,
+        };
+        Violations {
+            report,
+            policy,
+            current_idx: 1,
+            growing,
+        }
+    }
+}
+
+impl Iterator for Violations<'_> {
+    type Item = Violation;
+
+    fn next(&mut self) -> Option<Violation> {
+        while self.current_idx < self.report.len() {
+            let indices = (self.current_idx - 1, self.current_idx);
+            let prev = self.report[indices.0];
+            let current = self.report[indices.1];
+            self.current_idx += 1;
+
+            let kind = if !self.policy.is_valid_step(prev, current) {
+                if current.abs_diff(prev) > self.policy.max_step as u32 {
+                    Some(ViolationKind::TooLarge)
+                } else {
+                    Some(ViolationKind::TooSmall)
+                }
+            } else {
+                None
+            };
+
+            let kind = kind.or_else(|| {
+                if prev == current {
+                    return None;
+                }
+                match self.growing {
+                    Some(growing) if (prev < current) != growing => {
+                        Some(ViolationKind::DirectionChange)
+                    }
+                    _ => None,
+                }
+            });
+
+            if prev != current && self.growing.is_none() {
+                self.growing = Some(prev < current);
+            }
+
+            if let Some(kind) = kind {
+                return Some(Violation {
+                    kind,
+                    indices,
+                    values: (prev, current),
+                });
+            }
+        }
+        None
+    }
+}
+
+// The problem dampener may remove up to `tolerance` levels, as few as possible.
+pub fn evaluate_report(report: &[i32], policy: &SafetyPolicy, tolerance: usize) -> SafetyVerdict {
+    let violations: Vec<Violation> = Violations::new(report, policy).collect();
+    if violations.is_empty() {
+        return SafetyVerdict::Safe;
+    }
+
+    let removed = match tolerance {
+        0 => None,
+        1 => find_removable_level(report, policy).map(|index| vec![index]),
+        _ => is_safe_with_removals(report, policy, tolerance),
+    };
+    match removed {
+        Some(indices) => SafetyVerdict::SafeAfterRemoval { indices },
+        None => SafetyVerdict::Unsafe { violations },
+    }
+}
+
+// Renders the report with the offending levels highlighted, followed by an
+// explanation of the verdict.
+pub fn render_verdict(report: &[i32], verdict: &SafetyVerdict, color: bool) -> String {
+    let highlight = |idx: usize| -> Option<&str> {
+        match verdict {
+            SafetyVerdict::Safe => None,
+            SafetyVerdict::SafeAfterRemoval { indices } => indices.contains(&idx).then_some(YELLOW),
+            SafetyVerdict::Unsafe { violations } => violations
+                .iter()
+                .any(|violation| violation.indices.0 == idx || violation.indices.1 == idx)
+                .then_some(RED),
+        }
+    };
+
+    let levels: Vec<String> = report
+        .iter()
+        .enumerate()
+        .map(|(idx, level)| match highlight(idx) {
+            Some(code) if color => format!("{}{}{}", code, level, RESET),
+            Some(_) => format!("[{}]", level),
+            None => level.to_string(),
+        })
+        .collect();
+
+    let explanation = match verdict {
+        SafetyVerdict::Safe => "safe".to_string(),
+        SafetyVerdict::SafeAfterRemoval { indices } => {
+            let removed: Vec<String> = indices
+                .iter()
+                .map(|&index| format!("{} ({})", index, report[index]))
+                .collect();
+            let noun = if indices.len() == 1 {
+                "level"
+            } else {
+                "levels"
+            };
+            format!("safe after removing {} {}", noun, removed.join(", "))
+        }
+        SafetyVerdict::Unsafe { violations } => format!(
+            "unsafe: {}",
+            violations
+                .iter()
+                .map(|violation| violation.to_string())
+                .collect::<Vec<_>>()
+                .join("; ")
+        ),
+    };
+
+    format!("{} - {}", levels.join(" "), explanation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_violations_reports_every_bad_step() {
+        let policy = SafetyPolicy::default();
+        let violations: Vec<Violation> = Violations::new(&[1, 2, 7, 6, 6], &policy).collect();
+
+        assert_eq!(
+            violations,
+            vec![
+                Violation {
+                    kind: ViolationKind::TooLarge,
+                    indices: (1, 2),
+                    values: (2, 7),
+                },
+                Violation {
+                    kind: ViolationKind::DirectionChange,
+                    indices: (2, 3),
+                    values: (7, 6),
+                },
+                Violation {
+                    kind: ViolationKind::TooSmall,
+                    indices: (3, 4),
+                    values: (6, 6),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_violations_of_extreme_levels() {
+        let policy = SafetyPolicy::default();
+        let violations: Vec<Violation> =
+            Violations::new(&[i32::MIN, i32::MAX, i32::MIN], &policy).collect();
+
+        assert_eq!(violations.len(), 2);
+        assert!(violations
+            .iter()
+            .all(|violation| violation.kind == ViolationKind::TooLarge));
+    }
+
+    #[test]
+    fn test_evaluate_report() {
+        let policy = SafetyPolicy::default();
+
+        assert_eq!(
+            evaluate_report(&[7, 6, 4, 2, 1], &policy, 1),
+            SafetyVerdict::Safe
+        );
+        assert_eq!(
+            evaluate_report(&[1, 3, 2, 4, 5], &policy, 1),
+            SafetyVerdict::SafeAfterRemoval { indices: vec![1] }
+        );
+        assert!(matches!(
+            evaluate_report(&[1, 3, 2, 4, 5], &policy, 0),
+            SafetyVerdict::Unsafe { .. }
+        ));
+        assert!(matches!(
+            evaluate_report(&[1, 2, 7, 8, 9], &policy, 1),
+            SafetyVerdict::Unsafe { .. }
+        ));
+    }
+
+    #[test]
+    fn test_evaluate_report_with_tolerance() {
+        let policy = SafetyPolicy::default();
+        let report = [1, 9, 2, 9, 3];
+
+        assert!(matches!(
+            evaluate_report(&report, &policy, 1),
+            SafetyVerdict::Unsafe { .. }
+        ));
+        let verdict = evaluate_report(&report, &policy, 2);
+        assert_eq!(
+            verdict,
+            SafetyVerdict::SafeAfterRemoval {
+                indices: vec![1, 3]
+            }
+        );
+        assert_eq!(
+            render_verdict(&report, &verdict, false),
+            "1 [9] 2 [9] 3 - safe after removing levels 1 (9), 3 (9)"
+        );
+    }
+
+    #[test]
+    fn test_render_verdict_without_color() {
+        let policy = SafetyPolicy::default();
+        let report = [9, 7, 6, 2, 1];
+        let verdict = evaluate_report(&report, &policy, 1);
+
+        assert_eq!(
+            render_verdict(&report, &verdict, false),
+            "9 7 [6] [2] 1 - unsafe: step too large between levels 2 and 3 (6 -> 2)"
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use std::fmt;
+use std::ops::Range;
+
+use crate::lexer::{tokenize, TokenKind};
+use crate::parser::{candidates, parse_args, Signature, WhitespacePolicy};
+use crate::trace::{line_column, line_starts};
+
+// Why text that starts like an instruction is not one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MissReason {
+    // A known name with more characters before the parenthesis, e.g. `mulx(`.
+    UnknownName(String),
+    // Whitespace the policy does not allow, e.g. `mul (`.
+    Whitespace,
+    // An operand longer than three digits, e.g. `mul(1234,5)`.
+    TooManyDigits {
+        digits: usize,
+    },
+    // Any other token out of place, e.g. `mul(4*`. `found` is None at the
+    // end of the memory.
+    Unexpected {
+        expected: TokenKind,
+        found: Option<String>,
+    },
+}
+
+impl fmt::Display for MissReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MissReason::UnknownName(name) => write!(f, "unknown instruction `{}`", name),
+            MissReason::Whitespace => write!(f, "whitespace inside the instruction"),
+            MissReason::TooManyDigits { digits } => {
+                write!(f, "operand has {} digits, at most 3 are allowed", digits)
+            }
+            MissReason::Unexpected {
+                expected,
+                found: Some(found),
+            } => write!(f, "expected {} but found `{}`", describe(*expected), found),
+            MissReason::Unexpected {
+                expected,
+                found: None,
+            } => write!(f, "expected {} but the memory ends", describe(*expected)),
+        }
+    }
+}
+
+fn describe(kind: TokenKind) -> &'static str {
+    match kind {
+        TokenKind::Word => "a name",
+        TokenKind::Number => "a number",
+        TokenKind::LParen => "`(`",
+        TokenKind::RParen => "`)`",
+        TokenKind::Comma => "`,`",
+        TokenKind::Whitespace => "whitespace",
+        TokenKind::Other => "a symbol",
+    }
+}
+
+// Text that starts like an instruction but fails to parse. The span runs from
+// the name up to and including the offending token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NearMiss {
+    pub span: Range<usize>,
+    // 1-based position of the first byte, counting bytes.
+    pub line: usize,
+    pub column: usize,
+    pub reason: MissReason,
+}
+
+// Finds every near-miss of the given signatures. Text that does parse is
+// skipped exactly like `parse_calls` does, so a near-miss never overlaps a
+// recognised instruction.
+pub fn near_misses(
+    memory: &[u8],
+    signatures: &[Signature],
+    whitespace: WhitespacePolicy,
+) -> Vec<NearMiss> {
+    let tokens = tokenize(memory);
+    let line_starts = line_starts(memory);
+    let mut misses = vec![];
+    let mut push = |span: Range<usize>, reason| {
+        let (line, column) = line_column(&line_starts, span.start);
+        misses.push(NearMiss {
+            span,
+            line,
+            column,
+            reason,
+        });
+    };
+
+    let mut idx = 0;
+    while idx < tokens.len() {
+        let word = &tokens[idx];
+        if word.kind != TokenKind::Word {
+            idx += 1;
+            continue;
+        }
+        let text = &memory[word.span.clone()];
+
+        // The longest name decides the reason, like it is tried first.
+        let mut rejected = None;
+        let mut parsed = None;
+        for signature in candidates(text, signatures) {
+            match parse_args(
+                memory,
+                &tokens[idx..],
+                signatures[signature].arity,
+                whitespace,
+            ) {
+                Ok((_, consumed)) => {
+                    parsed = Some(consumed);
+                    break;
+                }
+                Err(rejection) => {
+                    rejected.get_or_insert((signature, rejection));
+                }
+            }
+        }
+
+        if let Some(consumed) = parsed {
+            idx += consumed;
+            continue;
+        }
+
+        if let Some((signature, rejection)) = rejected {
+            let start = word.span.end - signatures[signature].name.len();
+            let end = tokens[idx + rejection.consumed - 1].span.end;
+            push(start..end, rejection.reason);
+        } else if tokens.get(idx + 1).map(|token| token.kind) == Some(TokenKind::LParen)
+            && signatures.iter().any(|signature| {
+                text.windows(signature.name.len())
+                    .any(|window| window == signature.name.as_bytes())
+            })
+        {
+            push(
+                word.span.start..tokens[idx + 1].span.end,
+                MissReason::UnknownName(String::from_utf8_lossy(text).into()),
+            );
+        }
+        idx += 1;
+    }
+
+    misses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::puzzle_signatures;
+
+    fn misses(memory: &str) -> Vec<(Range<usize>, MissReason)> {
+        near_misses(
+            memory.as_bytes(),
+            &puzzle_signatures(),
+            WhitespacePolicy::Forbidden,
+        )
+        .into_iter()
+        .map(|miss| (miss.span, miss.reason))
+        .collect()
+    }
+
+    #[test]
+    fn test_near_misses() {
+        assert_eq!(
+            misses("mul(4*"),
+            vec![(
+                0..6,
+                MissReason::Unexpected {
+                    expected: TokenKind::Comma,
+                    found: Some("*".to_string()),
+                }
+            )]
+        );
+        assert_eq!(
+            misses("mul ( 2 , 4 )"),
+            vec![(0..4, MissReason::Whitespace)]
+        );
+        assert_eq!(
+            misses("mul(1234,5)"),
+            vec![(0..8, MissReason::TooManyDigits { digits: 4 })]
+        );
+        assert_eq!(
+            misses("x do_not()"),
+            vec![(2..9, MissReason::UnknownName("do_not".to_string()))]
+        );
+        assert_eq!(
+            misses("mul(2,"),
+            vec![(
+                0..6,
+                MissReason::Unexpected {
+                    expected: TokenKind::Number,
+                    found: None,
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_near_misses_skip_valid_instructions() {
+        let found = near_misses(
+            b"xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)\nundo()?mul(1,mul(8,5))",
+            &puzzle_signatures(),
+            WhitespacePolicy::Forbidden,
+        );
+
+        let positions: Vec<(usize, usize)> =
+            found.iter().map(|miss| (miss.line, miss.column)).collect();
+        assert_eq!(positions, vec![(1, 11), (1, 38), (2, 8)]);
+        assert_eq!(found[1].reason.to_string(), "expected `)` but found `]`");
+        assert_eq!(
+            found[2].reason.to_string(),
+            "expected a number but found `mul`"
+        );
+    }
+
+    #[test]
+    fn test_near_misses_follow_whitespace_policy() {
+        let found = near_misses(
+            b"mul(2, 4)mul(3,\n4)",
+            &puzzle_signatures(),
+            WhitespacePolicy::SameLine,
+        );
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].span, 9..16);
+        assert_eq!(found[0].reason, MissReason::Whitespace);
+    }
+}
@@ -0,0 +1,102 @@
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    // Run of letters, `_` and `'`, e.g. `mul`, `xmul` or `don't`.
+    Word,
+    // Run of ASCII digits.
+    Number,
+    LParen,
+    RParen,
+    Comma,
+    // Run of ASCII whitespace, including line breaks.
+    Whitespace,
+    // Any other character.
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Range<usize>,
+}
+
+fn is_word_byte(byte: u8) -> bool {
+    byte.is_ascii_alphabetic() || byte == b'_' || byte == b'\''
+}
+
+// Splits corrupted memory into tokens. Every byte ends up in exactly one
+// token, so the spans cover the whole input.
+pub fn tokenize(memory: &[u8]) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut start = 0;
+
+    while start < memory.len() {
+        let byte = memory[start];
+        let run_length = |matches: fn(u8) -> bool| {
+            memory[start..]
+                .iter()
+                .take_while(|&&byte| matches(byte))
+                .count()
+        };
+
+        let (kind, length) = match byte {
+            b'(' => (TokenKind::LParen, 1),
+            b')' => (TokenKind::RParen, 1),
+            b',' => (TokenKind::Comma, 1),
+            _ if byte.is_ascii_digit() => (TokenKind::Number, run_length(|b| b.is_ascii_digit())),
+            _ if is_word_byte(byte) => (TokenKind::Word, run_length(is_word_byte)),
+            _ if byte.is_ascii_whitespace() => (
+                TokenKind::Whitespace,
+                run_length(|b| b.is_ascii_whitespace()),
+            ),
+            _ => (TokenKind::Other, 1),
+        };
+
+        tokens.push(Token {
+            kind,
+            span: start..start + length,
+        });
+        start += length;
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        let kinds: Vec<TokenKind> = tokenize(b"xmul(2,48)!don't \n\t")
+            .into_iter()
+            .map(|token| token.kind)
+            .collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Word,
+                TokenKind::LParen,
+                TokenKind::Number,
+                TokenKind::Comma,
+                TokenKind::Number,
+                TokenKind::RParen,
+                TokenKind::Other,
+                TokenKind::Word,
+                TokenKind::Whitespace,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_spans() {
+        let spans: Vec<Range<usize>> = tokenize(b"mul(123,4)")
+            .into_iter()
+            .map(|token| token.span)
+            .collect();
+
+        assert_eq!(spans, vec![0..3, 3..4, 4..7, 7..8, 8..9, 9..10]);
+    }
+}
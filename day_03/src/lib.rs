@@ -0,0 +1,251 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead};
+use std::ops::Range;
+use std::path::Path;
+use std::str::FromStr;
+
+use memmap2::Mmap;
+
+pub mod diagnostics;
+pub mod lexer;
+pub mod parser;
+pub mod stream;
+pub mod trace;
+pub mod vm;
+
+pub use diagnostics::{near_misses, MissReason, NearMiss};
+pub use parser::{
+    parse, parse_calls, parse_with, puzzle_signatures, Call, Instruction, Signature,
+    SpannedInstruction, WhitespacePolicy,
+};
+pub use stream::{stream_instructions, sum_reader, StreamParser, Sums};
+pub use trace::{render_ansi, render_html, render_trace, trace, TraceEntry};
+pub use vm::{Block, InstructionTable, Machine, Opcode, VmError, VmErrorKind};
+
+// Splits the memory into its non-empty lines. Instructions can not span
+// several lines in this mode.
+pub fn read_memory_data(file_path: &str) -> io::Result<Vec<String>> {
+    let path = Path::new(file_path);
+    let file = File::open(path)?;
+    let reader = io::BufReader::new(file);
+
+    let mut memory = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if !line.is_empty() {
+            memory.push(line);
+        }
+    }
+    Ok(memory)
+}
+
+// Reads the whole file as one continuous byte stream, line breaks included.
+pub fn read_memory_bytes(file_path: &str) -> io::Result<Vec<u8>> {
+    fs::read(Path::new(file_path))
+}
+
+/// Maps the file into memory instead of reading it, for large dumps.
+///
+/// # Safety
+///
+/// The file must not be modified or truncated, by this process or any other,
+/// while the returned map is alive. Otherwise the bytes behind the map may
+/// change under shared references, or reading them may crash the process.
+pub unsafe fn map_memory(file_path: &str) -> io::Result<Mmap> {
+    let file = File::open(Path::new(file_path))?;
+    // SAFETY: forwarded to the caller, see above.
+    unsafe { Mmap::map(&file) }
+}
+
+// Integer type the products are summed into.
+pub trait Accumulator: Clone + PartialEq + fmt::Debug + fmt::Display {
+    fn zero() -> Self;
+    // `self + first * second`, None on overflow.
+    fn checked_add_product(&self, first: i32, second: i32) -> Option<Self>;
+}
+
+macro_rules! impl_accumulator {
+    ($($t:ty),*) => {
+        $(
+            impl Accumulator for $t {
+                fn zero() -> Self {
+                    0
+                }
+
+                fn checked_add_product(&self, first: i32, second: i32) -> Option<Self> {
+                    <$t>::from(first)
+                        .checked_mul(<$t>::from(second))
+                        .and_then(|product| self.checked_add(product))
+                }
+            }
+        )*
+    };
+}
+
+impl_accumulator!(i32, i64, i128);
+
+#[cfg(feature = "bigint")]
+impl Accumulator for num_bigint::BigInt {
+    fn zero() -> Self {
+        num_bigint::BigInt::from(0)
+    }
+
+    fn checked_add_product(&self, first: i32, second: i32) -> Option<Self> {
+        Some(self + num_bigint::BigInt::from(first) * second)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntType {
+    I32,
+    I64,
+    I128,
+    // Arbitrary precision, only with the `bigint` feature.
+    #[cfg(feature = "bigint")]
+    BigInt,
+}
+
+impl FromStr for IntType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "i32" => Ok(IntType::I32),
+            "i64" => Ok(IntType::I64),
+            "i128" => Ok(IntType::I128),
+            #[cfg(feature = "bigint")]
+            "bigint" => Ok(IntType::BigInt),
+            #[cfg(not(feature = "bigint"))]
+            "bigint" => Err("bigint support requires the `bigint` feature".to_string()),
+            _ => Err(format!("Unsupported integer type: {}", s)),
+        }
+    }
+}
+
+// The sum left the range of the accumulator at the instruction with this span.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverflowError {
+    pub span: Range<usize>,
+}
+
+impl fmt::Display for OverflowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sum overflowed at bytes {}..{}",
+            self.span.start, self.span.end
+        )
+    }
+}
+
+impl std::error::Error for OverflowError {}
+
+fn add_product<T: Accumulator>(
+    sum: &T,
+    first: i32,
+    second: i32,
+    parsed: &SpannedInstruction,
+) -> Result<T, OverflowError> {
+    sum.checked_add_product(first, second)
+        .ok_or_else(|| OverflowError {
+            span: parsed.span.clone(),
+        })
+}
+
+// Part 1: every `mul` counts.
+pub fn sum_of_products<T: Accumulator>(
+    instructions: &[SpannedInstruction],
+) -> Result<T, OverflowError> {
+    instructions
+        .iter()
+        .try_fold(T::zero(), |sum, parsed| match parsed.instruction {
+            Instruction::Mul(first, second) => add_product(&sum, first, second, parsed),
+            _ => Ok(sum),
+        })
+}
+
+// Part 2: `don't()` disables the following `mul`s until the next `do()`.
+pub fn sum_of_enabled_products<T: Accumulator>(
+    instructions: &[SpannedInstruction],
+) -> Result<T, OverflowError> {
+    let (sum, _) = instructions
+        .iter()
+        .try_fold((T::zero(), true), |(sum, do_mul), parsed| {
+            Ok(match parsed.instruction {
+                Instruction::Mul(first, second) if do_mul => {
+                    (add_product(&sum, first, second, parsed)?, do_mul)
+                }
+                Instruction::Mul(_, _) => (sum, do_mul),
+                Instruction::Do => (sum, true),
+                Instruction::Dont => (sum, false),
+            })
+        })?;
+    Ok(sum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_sums_of_example() {
+        let instructions =
+            parse(b"xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))");
+
+        assert_eq!(sum_of_products(&instructions), Ok(161i64));
+        assert_eq!(sum_of_enabled_products(&instructions), Ok(48i64));
+    }
+
+    #[test]
+    fn test_sum_overflow() {
+        let mut memory = "mul(999,999)".repeat(2151);
+        memory.push_str("don't()mul(999,999)");
+        let instructions = parse(memory.as_bytes());
+
+        assert_eq!(
+            sum_of_products::<i32>(&instructions),
+            Err(OverflowError { span: 25819..25831 })
+        );
+        assert_eq!(
+            sum_of_enabled_products::<i32>(&instructions),
+            Ok(2146700151)
+        );
+        assert_eq!(sum_of_products::<i64>(&instructions), Ok(998001 * 2152));
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_bigint_sum() {
+        let instructions = parse(b"mul(2,4)don't()mul(5,5)do()mul(8,5)");
+
+        assert_eq!(
+            sum_of_products::<num_bigint::BigInt>(&instructions),
+            Ok(num_bigint::BigInt::from(73))
+        );
+    }
+
+    #[test]
+    fn test_memory_as_byte_stream() {
+        let path = env::temp_dir().join(format!("day_03_stream_{}.txt", std::process::id()));
+        fs::write(&path, "mul(2,3)don't()\n\nmul(12,\n34)do()mul(1,1)\n").unwrap();
+        let path = path.to_str().unwrap();
+
+        let bytes = read_memory_bytes(path).unwrap();
+        // SAFETY: the file belongs to this test and is only removed once the
+        // map is no longer used.
+        let mapped = unsafe { map_memory(path) }.unwrap();
+        assert_eq!(&mapped[..], &bytes[..]);
+        drop(mapped);
+        fs::remove_file(path).unwrap();
+
+        let strict = parse_with(&bytes, WhitespacePolicy::Forbidden);
+        assert_eq!(sum_of_products(&strict), Ok(7i64));
+        assert_eq!(sum_of_enabled_products(&strict), Ok(7i64));
+
+        let relaxed = parse_with(&bytes, WhitespacePolicy::Anywhere);
+        assert_eq!(sum_of_products(&relaxed), Ok(7i64 + 408));
+        assert_eq!(sum_of_enabled_products(&relaxed), Ok(7i64));
+    }
+}
@@ -1,83 +1,239 @@
-use regex::Regex;
+use std::env;
 use std::fs::File;
-use std::io::{self, BufRead};
-use std::path::Path;
-use std::str::FromStr;
-use std::{env, mem};
+use std::io::{self, Read};
+
+use day_03::{
+    map_memory, near_misses, parse_with, puzzle_signatures, read_memory_bytes, read_memory_data,
+    render_ansi, render_html, render_trace, sum_of_enabled_products, sum_of_products, sum_reader,
+    trace, Accumulator, InstructionTable, IntType, Machine, SpannedInstruction, WhitespacePolicy,
+};
 
 fn main() {
     // Get the file path from command-line arguments
     let args: Vec<String> = env::args().collect();
     if args.len() < 2 {
-        eprintln!("Usage: {} <file_path>", args[0]);
+        eprintln!("Usage: {} <file_path> [options]", args[0]);
+        eprintln!("  --extended            also run the memory with the extended instruction set");
+        eprintln!("  --stream              scan the whole file as one byte stream");
+        eprintln!("  --mmap                like --stream, but memory-map the file, which must stay unchanged");
+        eprintln!("  --whitespace <policy> whitespace inside instructions: none, spaces or any");
+        eprintln!("  --int-type <type>     sum type: i32, i64 (default), i128 or bigint");
+        eprintln!("  --trace               list every instruction of part 2 (implies --stream)");
+        eprintln!(
+            "  --annotate <format>   highlight the memory as ansi or html (implies --stream)"
+        );
+        eprintln!("  --near-misses         list almost-valid instructions (implies --stream)");
+        eprintln!("  --chunk-size <bytes>  sum while reading the file in chunks, `-` reads stdin");
         std::process::exit(1);
     }
     let file_path = &args[1];
 
-    let memory = read_memory_data(file_path).expect("Failed to read and parse file");
+    let options = parse_options(&args[2..]).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+
+    if let Some(chunk_size) = options.chunk_size {
+        let reader: Box<dyn Read> = if file_path == "-" {
+            Box::new(io::stdin().lock())
+        } else {
+            Box::new(File::open(file_path).expect("Failed to read and parse file"))
+        };
+        match options.int_type {
+            IntType::I32 => print_stream_sums::<i32>(reader, options.whitespace, chunk_size),
+            IntType::I64 => print_stream_sums::<i64>(reader, options.whitespace, chunk_size),
+            IntType::I128 => print_stream_sums::<i128>(reader, options.whitespace, chunk_size),
+            #[cfg(feature = "bigint")]
+            IntType::BigInt => {
+                print_stream_sums::<num_bigint::BigInt>(reader, options.whitespace, chunk_size)
+            }
+        }
+        return;
+    }
+
+    // In stream mode the whole file is a single chunk, otherwise every
+    // non-empty line is one. The enabled state carries over between chunks.
+    let (rows, bytes, mapped);
+    let chunks: Vec<&[u8]> = if options.mmap {
+        // SAFETY: --mmap asks for the file to stay unchanged while it is
+        // scanned, see the usage.
+        mapped = unsafe { map_memory(file_path) }.expect("Failed to read and parse file");
+        vec![&mapped[..]]
+    } else if options.stream {
+        bytes = read_memory_bytes(file_path).expect("Failed to read and parse file");
+        vec![&bytes[..]]
+    } else {
+        rows = read_memory_data(file_path).expect("Failed to read and parse file");
+        println!("Memory rows found: {:?}", rows.len());
+        rows.iter().map(|row| row.as_bytes()).collect()
+    };
 
-    println!("Memory rows found: {:?}", memory.len());
+    let instructions: Vec<_> = chunks
+        .iter()
+        .flat_map(|chunk| parse_with(chunk, options.whitespace))
+        .collect();
 
-    println!("==================== Part 1 ====================");
-    let mul_regex = Regex::new(r"mul\((?<first>\d+),(?<second>\d+)\)").unwrap();
-
-    let mut sum: i32 = 0;
-
-    for s in memory.iter() {
-        for mul_capture in mul_regex.captures_iter(s.as_str()) {
-            let first: i32 = mul_capture.name("first").unwrap().as_str().parse().unwrap();
-            let second: i32 = mul_capture
-                .name("second")
-                .unwrap()
-                .as_str()
-                .parse()
-                .unwrap();
-            sum += first * second;
+    if options.near_misses {
+        let memory = chunks[0];
+        let misses = near_misses(memory, &puzzle_signatures(), options.whitespace);
+        for miss in &misses {
+            println!(
+                "{}:{} `{}`: {}",
+                miss.line,
+                miss.column,
+                String::from_utf8_lossy(&memory[miss.span.clone()]),
+                miss.reason
+            );
         }
+        println!("Near-misses found: {}", misses.len());
+        return;
     }
-    println!("Sum: {}", sum);
 
-    println!("==================== Part 2 ====================");
-    let mul_regex_with_dos =
-        Regex::new(r"mul\((?<first>\d+),(?<second>\d+)\)|do\(\)|don't\(\)").unwrap();
-
-    let mut do_mul = true;
-    sum = 0;
-    for s in memory.iter() {
-        for command_capture in mul_regex_with_dos.captures_iter(s.as_str()) {
-            if let Some(mul_capture) = command_capture.name("first") {
-                if do_mul {
-                    let first: i32 = mul_capture.as_str().parse().unwrap();
-                    let second: i32 = command_capture
-                        .name("second")
-                        .unwrap()
-                        .as_str()
-                        .parse()
-                        .unwrap();
-                    sum += first * second;
-                }
-            } else if command_capture.get(0).unwrap().as_str() == "do()" {
-                do_mul = true;
-            } else if command_capture.get(0).unwrap().as_str() == "don't()" {
-                do_mul = false;
+    if options.trace || options.annotate.is_some() {
+        let memory = chunks[0];
+        let entries = trace(memory, &instructions).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        });
+        match options.annotate {
+            Some(Annotation::Ansi) => println!("{}", render_ansi(memory, &entries)),
+            Some(Annotation::Html) => print!("{}", render_html(memory, &entries)),
+            None => print!("{}", render_trace(memory, &entries)),
+        }
+        return;
+    }
+
+    match options.int_type {
+        IntType::I32 => print_sums::<i32>(&instructions),
+        IntType::I64 => print_sums::<i64>(&instructions),
+        IntType::I128 => print_sums::<i128>(&instructions),
+        #[cfg(feature = "bigint")]
+        IntType::BigInt => print_sums::<num_bigint::BigInt>(&instructions),
+    }
+
+    if options.extended {
+        let mut table = InstructionTable::extended();
+        table.set_whitespace(options.whitespace);
+        let mut machine = Machine::new();
+        for (idx, chunk) in chunks.iter().enumerate() {
+            if let Err(err) = table.execute(&mut machine, chunk) {
+                eprintln!("Chunk {}: {}", idx + 1, err);
+                std::process::exit(1);
             }
         }
+
+        println!("==================== Extended ====================");
+        println!("Sum: {}", machine.accumulator);
+        println!("Stack: {:?}", machine.stack);
+    }
+}
+
+fn print_sums<T: Accumulator>(instructions: &[SpannedInstruction]) {
+    println!("==================== Part 1 ====================");
+    match sum_of_products::<T>(instructions) {
+        Ok(sum) => println!("Sum: {}", sum),
+        Err(err) => eprintln!("Sum: {}", err),
     }
 
-    println!("Sum: {}", sum);
+    println!("==================== Part 2 ====================");
+    match sum_of_enabled_products::<T>(instructions) {
+        Ok(sum) => println!("Sum: {}", sum),
+        Err(err) => eprintln!("Sum: {}", err),
+    }
+}
+
+enum Annotation {
+    Ansi,
+    Html,
+}
+
+fn print_stream_sums<T: Accumulator>(
+    reader: impl Read,
+    whitespace: WhitespacePolicy,
+    chunk_size: usize,
+) {
+    let sums =
+        sum_reader::<T, _>(reader, whitespace, chunk_size).expect("Failed to read and parse file");
+
+    println!("==================== Part 1 ====================");
+    match sums.products {
+        Ok(sum) => println!("Sum: {}", sum),
+        Err(err) => eprintln!("Sum: {}", err),
+    }
+
+    println!("==================== Part 2 ====================");
+    match sums.enabled_products {
+        Ok(sum) => println!("Sum: {}", sum),
+        Err(err) => eprintln!("Sum: {}", err),
+    }
 }
 
-fn read_memory_data(file_path: &str) -> io::Result<Vec<String>> {
-    let path = Path::new(file_path);
-    let file = File::open(&path)?;
-    let reader = io::BufReader::new(file);
+struct Options {
+    extended: bool,
+    stream: bool,
+    mmap: bool,
+    whitespace: WhitespacePolicy,
+    int_type: IntType,
+    trace: bool,
+    annotate: Option<Annotation>,
+    near_misses: bool,
+    chunk_size: Option<usize>,
+}
 
-    let mut memory = Vec::new();
-    for line in reader.lines() {
-        let line = line?;
-        if !line.is_empty() {
-            memory.push(line);
+fn parse_options(options: &[String]) -> Result<Options, String> {
+    let mut parsed = Options {
+        extended: false,
+        stream: false,
+        mmap: false,
+        whitespace: WhitespacePolicy::Forbidden,
+        int_type: IntType::I64,
+        trace: false,
+        annotate: None,
+        near_misses: false,
+        chunk_size: None,
+    };
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--extended" => parsed.extended = true,
+            "--stream" => parsed.stream = true,
+            "--mmap" => parsed.mmap = true,
+            "--trace" => parsed.trace = true,
+            "--near-misses" => parsed.near_misses = true,
+            "--annotate" => {
+                parsed.annotate = match options.next().map(String::as_str) {
+                    Some("ansi") => Some(Annotation::Ansi),
+                    Some("html") => Some(Annotation::Html),
+                    value => return Err(format!("Invalid annotation: {}", value.unwrap_or(""))),
+                };
+            }
+            "--whitespace" => {
+                let value = options
+                    .next()
+                    .ok_or_else(|| format!("Missing value for {}", option))?;
+                parsed.whitespace = value.parse()?;
+            }
+            "--chunk-size" => {
+                let value = options
+                    .next()
+                    .ok_or_else(|| format!("Missing value for {}", option))?;
+                parsed.chunk_size = match value.parse() {
+                    Ok(size) if size > 0 => Some(size),
+                    _ => return Err(format!("Invalid chunk size: {}", value)),
+                };
+            }
+            "--int-type" => {
+                let value = options
+                    .next()
+                    .ok_or_else(|| format!("Missing value for {}", option))?;
+                parsed.int_type = value.parse()?;
+            }
+            _ => return Err(format!("Unknown option: {}", option)),
         }
     }
-    Ok(memory)
+    // Positions in the trace refer to the whole file
+    if parsed.trace || parsed.annotate.is_some() || parsed.near_misses {
+        parsed.stream = true;
+    }
+    Ok(parsed)
 }
@@ -0,0 +1,331 @@
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::diagnostics::MissReason;
+use crate::lexer::{tokenize, Token, TokenKind};
+
+const MAX_OPERAND_DIGITS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Mul(i32, i32),
+    Do,
+    Dont,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpannedInstruction {
+    pub instruction: Instruction,
+    pub span: Range<usize>,
+}
+
+// Whether whitespace may appear between the tokens of an instruction, i.e.
+// after its name, around the parentheses and around the commas. Whitespace is
+// never allowed inside a name or an operand. The puzzle forbids it entirely.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WhitespacePolicy {
+    #[default]
+    Forbidden,
+    // Spaces and tabs, but no line breaks, so `mul(2, 4)` is valid while an
+    // instruction still has to fit on one line.
+    SameLine,
+    // Any whitespace, so `mul(12,\n34)` is valid as well.
+    Anywhere,
+}
+
+impl WhitespacePolicy {
+    fn allows(self, whitespace: &[u8]) -> bool {
+        match self {
+            WhitespacePolicy::Forbidden => false,
+            WhitespacePolicy::SameLine => !whitespace.iter().any(|&b| b == b'\n' || b == b'\r'),
+            WhitespacePolicy::Anywhere => true,
+        }
+    }
+}
+
+impl FromStr for WhitespacePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(WhitespacePolicy::Forbidden),
+            "spaces" => Ok(WhitespacePolicy::SameLine),
+            "any" => Ok(WhitespacePolicy::Anywhere),
+            _ => Err(format!("Invalid whitespace policy: {}", s)),
+        }
+    }
+}
+
+// Name and number of operands of an instruction, e.g. `mul` takes two.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    pub name: String,
+    pub arity: usize,
+}
+
+impl Signature {
+    pub fn new(name: &str, arity: usize) -> Signature {
+        Signature {
+            name: name.to_string(),
+            arity,
+        }
+    }
+}
+
+// An instruction found in memory, referring to its signature by index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Call {
+    pub signature: usize,
+    pub args: Vec<i32>,
+    pub span: Range<usize>,
+}
+
+// The instructions of the puzzle, in the order of `Instruction`.
+pub fn puzzle_signatures() -> Vec<Signature> {
+    vec![
+        Signature::new("mul", 2),
+        Signature::new("do", 0),
+        Signature::new("don't", 0),
+    ]
+}
+
+// Finds every valid `mul`, `do` and `don't` instruction in the corrupted memory.
+pub fn parse(memory: &[u8]) -> Vec<SpannedInstruction> {
+    parse_with(memory, WhitespacePolicy::Forbidden)
+}
+
+pub fn parse_with(memory: &[u8], whitespace: WhitespacePolicy) -> Vec<SpannedInstruction> {
+    parse_calls(memory, &puzzle_signatures(), whitespace)
+        .into_iter()
+        .map(puzzle_instruction)
+        .collect()
+}
+
+// Converts a call of one of the `puzzle_signatures`.
+pub(crate) fn puzzle_instruction(call: Call) -> SpannedInstruction {
+    let instruction = match call.signature {
+        0 => Instruction::Mul(call.args[0], call.args[1]),
+        1 => Instruction::Do,
+        _ => Instruction::Dont,
+    };
+    SpannedInstruction {
+        instruction,
+        span: call.span,
+    }
+}
+
+// Finds every call of one of the given signatures. Like the puzzle, a call
+// may directly follow any other character, so `xmul(2,4)` still contains
+// `mul(2,4)`, but nothing except whitespace allowed by the policy may appear
+// inside it.
+pub fn parse_calls(
+    memory: &[u8],
+    signatures: &[Signature],
+    whitespace: WhitespacePolicy,
+) -> Vec<Call> {
+    let tokens = tokenize(memory);
+    let mut calls = vec![];
+
+    let mut idx = 0;
+    while idx < tokens.len() {
+        match parse_call(memory, &tokens[idx..], signatures, whitespace) {
+            Some((call, consumed)) => {
+                calls.push(call);
+                idx += consumed;
+            }
+            None => idx += 1,
+        }
+    }
+
+    calls
+}
+
+fn parse_call(
+    memory: &[u8],
+    tokens: &[Token],
+    signatures: &[Signature],
+    whitespace: WhitespacePolicy,
+) -> Option<(Call, usize)> {
+    let word = tokens
+        .first()
+        .filter(|token| token.kind == TokenKind::Word)?;
+
+    candidates(&memory[word.span.clone()], signatures)
+        .into_iter()
+        .find_map(|signature| {
+            let (args, consumed) =
+                parse_args(memory, tokens, signatures[signature].arity, whitespace).ok()?;
+            let start = word.span.end - signatures[signature].name.len();
+            let span = start..tokens[consumed - 1].span.end;
+            Some((
+                Call {
+                    signature,
+                    args,
+                    span,
+                },
+                consumed,
+            ))
+        })
+}
+
+// Signatures whose name ends the word. A name may end a longer word. The
+// longest matching name comes first, so `don't` is never read as `do`.
+pub(crate) fn candidates(word: &[u8], signatures: &[Signature]) -> Vec<usize> {
+    let mut candidates: Vec<usize> = (0..signatures.len())
+        .filter(|&idx| word.ends_with(signatures[idx].name.as_bytes()))
+        .collect();
+    candidates.sort_by_key(|&idx| std::cmp::Reverse(signatures[idx].name.len()));
+    candidates
+}
+
+// Why the tokens after a name do not form a call. `consumed` counts the tokens
+// up to and including the offending one, the name included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Rejection {
+    pub(crate) reason: MissReason,
+    pub(crate) consumed: usize,
+}
+
+// Parses `(a,b,...)` right after the name token. Returns the operands and the
+// number of tokens used, including the name.
+pub(crate) fn parse_args(
+    memory: &[u8],
+    tokens: &[Token],
+    arity: usize,
+    whitespace: WhitespacePolicy,
+) -> Result<(Vec<i32>, usize), Rejection> {
+    let mut expected = vec![TokenKind::LParen];
+    for idx in 0..arity {
+        if idx > 0 {
+            expected.push(TokenKind::Comma);
+        }
+        expected.push(TokenKind::Number);
+    }
+    expected.push(TokenKind::RParen);
+
+    let mut args = vec![];
+    let mut consumed = 1;
+    for kind in expected {
+        let reject = |reason, consumed| Err(Rejection { reason, consumed });
+        let unexpected = |token: Option<&Token>| MissReason::Unexpected {
+            expected: kind,
+            found: token.map(|token| String::from_utf8_lossy(&memory[token.span.clone()]).into()),
+        };
+
+        let mut token = match tokens.get(consumed) {
+            Some(token) => token,
+            None => return reject(unexpected(None), consumed),
+        };
+        if token.kind == TokenKind::Whitespace {
+            if !whitespace.allows(&memory[token.span.clone()]) {
+                return reject(MissReason::Whitespace, consumed + 1);
+            }
+            consumed += 1;
+            token = match tokens.get(consumed) {
+                Some(token) => token,
+                None => return reject(unexpected(None), consumed),
+            };
+        }
+        if token.kind != kind {
+            return reject(unexpected(Some(token)), consumed + 1);
+        }
+        if kind == TokenKind::Number {
+            match parse_operand(memory, token) {
+                Some(operand) => args.push(operand),
+                None => {
+                    let digits = token.span.len();
+                    return reject(MissReason::TooManyDigits { digits }, consumed + 1);
+                }
+            }
+        }
+        consumed += 1;
+    }
+    Ok((args, consumed))
+}
+
+// Operands have at most three digits, so they never overflow.
+fn parse_operand(memory: &[u8], token: &Token) -> Option<i32> {
+    if token.span.len() > MAX_OPERAND_DIGITS {
+        return None;
+    }
+    memory[token.span.clone()]
+        .iter()
+        .try_fold(0, |value, &digit| Some(value * 10 + (digit - b'0') as i32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instructions(memory: &str) -> Vec<Instruction> {
+        parse(memory.as_bytes())
+            .into_iter()
+            .map(|parsed| parsed.instruction)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_example() {
+        assert_eq!(
+            instructions(
+                "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))"
+            ),
+            vec![
+                Instruction::Mul(2, 4),
+                Instruction::Dont,
+                Instruction::Mul(5, 5),
+                Instruction::Mul(11, 8),
+                Instruction::Do,
+                Instruction::Mul(8, 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_spans() {
+        let parsed = parse(b"xmul(2,4)don't()");
+
+        assert_eq!(parsed[0].span, 1..9);
+        assert_eq!(parsed[1].span, 9..16);
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_instructions() {
+        assert_eq!(instructions("mul(1234,5)"), vec![]);
+        assert_eq!(instructions("mul ( 2 , 4 )"), vec![]);
+        assert_eq!(instructions("mul(4*"), vec![]);
+        assert_eq!(instructions("mulx(2,4)"), vec![]);
+        assert_eq!(instructions("do_not()"), vec![]);
+        assert_eq!(instructions("mul(1,mul(2,3)"), vec![Instruction::Mul(2, 3)]);
+        assert_eq!(instructions("mul(999,0)"), vec![Instruction::Mul(999, 0)]);
+    }
+
+    #[test]
+    fn test_parse_whitespace_policy() {
+        let memory = b"mul(1, 2)mul ( 3 ,4 )mul(12,\n34)mul(5 6,1)";
+        let instructions = |policy| -> Vec<Instruction> {
+            parse_with(memory, policy)
+                .into_iter()
+                .map(|parsed| parsed.instruction)
+                .collect()
+        };
+
+        assert_eq!(instructions(WhitespacePolicy::Forbidden), vec![]);
+        assert_eq!(
+            instructions(WhitespacePolicy::SameLine),
+            vec![Instruction::Mul(1, 2), Instruction::Mul(3, 4)]
+        );
+        assert_eq!(
+            instructions(WhitespacePolicy::Anywhere),
+            vec![
+                Instruction::Mul(1, 2),
+                Instruction::Mul(3, 4),
+                Instruction::Mul(12, 34),
+            ]
+        );
+        assert_eq!(
+            parse_with(memory, WhitespacePolicy::Anywhere)[2].span,
+            21..32
+        );
+    }
+}
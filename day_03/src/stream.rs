@@ -0,0 +1,294 @@
+use std::collections::VecDeque;
+use std::io::{self, Read};
+
+use crate::lexer::{tokenize, Token, TokenKind};
+use crate::parser::{
+    candidates, parse_args, puzzle_instruction, puzzle_signatures, Call, Signature,
+    WhitespacePolicy,
+};
+use crate::{add_product, Accumulator, Instruction, OverflowError, SpannedInstruction};
+
+// Parses calls out of any reader without loading it, reading `chunk_size`
+// bytes at a time. Only the tail of the input that may still belong to an
+// unfinished call is kept between reads, so the calls and their spans are the
+// same as those of `parse_calls` on the whole input.
+pub struct StreamParser<R> {
+    reader: R,
+    signatures: Vec<Signature>,
+    whitespace: WhitespacePolicy,
+    chunk_size: usize,
+    buffer: Vec<u8>,
+    // Position of the first buffered byte in the whole input.
+    offset: usize,
+    pending: VecDeque<Call>,
+    eof: bool,
+}
+
+impl<R: Read> StreamParser<R> {
+    pub fn new(
+        reader: R,
+        signatures: Vec<Signature>,
+        whitespace: WhitespacePolicy,
+        chunk_size: usize,
+    ) -> StreamParser<R> {
+        StreamParser {
+            reader,
+            signatures,
+            whitespace,
+            chunk_size: chunk_size.max(1),
+            buffer: vec![],
+            offset: 0,
+            pending: VecDeque::new(),
+            eof: false,
+        }
+    }
+
+    fn read_chunk(&mut self) -> io::Result<()> {
+        let start = self.buffer.len();
+        self.buffer.resize(start + self.chunk_size, 0);
+        let read = loop {
+            match self.reader.read(&mut self.buffer[start..]) {
+                Ok(read) => break read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    self.buffer.truncate(start);
+                    return Err(err);
+                }
+            }
+        };
+        self.buffer.truncate(start + read);
+        self.eof = read == 0;
+        Ok(())
+    }
+
+    // Moves every call that more input can no longer change to `pending` and
+    // drops the bytes before the first one that still could.
+    fn scan(&mut self) {
+        let tokens = tokenize(&self.buffer);
+        let mut keep_from = self.buffer.len();
+        // Names only ever end a word, so of a word that is kept only its last
+        // bytes matter, however long it grows.
+        let longest_name = self
+            .signatures
+            .iter()
+            .map(|signature| signature.name.len())
+            .max()
+            .unwrap_or(0);
+        let name_start = |word: &Token| {
+            word.span
+                .start
+                .max(word.span.end.saturating_sub(longest_name))
+        };
+
+        let mut idx = 0;
+        'tokens: while idx < tokens.len() {
+            let word = &tokens[idx];
+            if word.kind != TokenKind::Word {
+                idx += 1;
+                continue;
+            }
+            // The word itself may still grow.
+            if !self.eof && word.span.end == self.buffer.len() {
+                keep_from = name_start(word);
+                break;
+            }
+
+            let text = &self.buffer[word.span.clone()];
+            for signature in candidates(text, &self.signatures) {
+                let arity = self.signatures[signature].arity;
+                match parse_args(&self.buffer, &tokens[idx..], arity, self.whitespace) {
+                    Ok((args, consumed)) => {
+                        let start = word.span.end - self.signatures[signature].name.len();
+                        let end = tokens[idx + consumed - 1].span.end;
+                        self.pending.push_back(Call {
+                            signature,
+                            args,
+                            span: self.offset + start..self.offset + end,
+                        });
+                        idx += consumed;
+                        continue 'tokens;
+                    }
+                    // Rejected at the last buffered token, which may still
+                    // change or be followed by the rest of the call.
+                    Err(rejection) if !self.eof && idx + rejection.consumed >= tokens.len() => {
+                        keep_from = name_start(word);
+                        break 'tokens;
+                    }
+                    Err(_) => {}
+                }
+            }
+            idx += 1;
+        }
+
+        self.buffer.drain(..keep_from);
+        self.offset += keep_from;
+    }
+}
+
+impl<R: Read> Iterator for StreamParser<R> {
+    type Item = io::Result<Call>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() && !self.eof {
+            if let Err(err) = self.read_chunk() {
+                return Some(Err(err));
+            }
+            self.scan();
+        }
+        self.pending.pop_front().map(Ok)
+    }
+}
+
+// `mul`, `do` and `don't` instructions of a reader, like `parse_with`.
+pub fn stream_instructions<R: Read>(
+    reader: R,
+    whitespace: WhitespacePolicy,
+    chunk_size: usize,
+) -> impl Iterator<Item = io::Result<SpannedInstruction>> {
+    StreamParser::new(reader, puzzle_signatures(), whitespace, chunk_size)
+        .map(|call| call.map(puzzle_instruction))
+}
+
+// Both parts summed in one pass, each stopping at its own overflow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sums<T> {
+    pub products: Result<T, OverflowError>,
+    pub enabled_products: Result<T, OverflowError>,
+    pub enabled: bool,
+}
+
+impl<T: Accumulator> Sums<T> {
+    pub fn new() -> Sums<T> {
+        Sums {
+            products: Ok(T::zero()),
+            enabled_products: Ok(T::zero()),
+            enabled: true,
+        }
+    }
+
+    pub fn add(&mut self, parsed: &SpannedInstruction) {
+        match parsed.instruction {
+            Instruction::Mul(first, second) => {
+                if let Ok(sum) = &self.products {
+                    self.products = add_product(sum, first, second, parsed);
+                }
+                if let (true, Ok(sum)) = (self.enabled, &self.enabled_products) {
+                    self.enabled_products = add_product(sum, first, second, parsed);
+                }
+            }
+            Instruction::Do => self.enabled = true,
+            Instruction::Dont => self.enabled = false,
+        }
+    }
+}
+
+impl<T: Accumulator> Default for Sums<T> {
+    fn default() -> Self {
+        Sums::new()
+    }
+}
+
+// Answers both parts for a reader of any size in constant memory, apart from
+// a single unfinished instruction.
+pub fn sum_reader<T: Accumulator, R: Read>(
+    reader: R,
+    whitespace: WhitespacePolicy,
+    chunk_size: usize,
+) -> io::Result<Sums<T>> {
+    let mut sums = Sums::new();
+    for parsed in stream_instructions(reader, whitespace, chunk_size) {
+        sums.add(&parsed?);
+    }
+    Ok(sums)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_with, sum_of_enabled_products, sum_of_products};
+    use proptest::prelude::*;
+
+    const EXAMPLE: &[u8] =
+        b"xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+
+    #[test]
+    fn test_stream_matches_in_memory_parser() {
+        let expected = parse_with(EXAMPLE, WhitespacePolicy::Forbidden);
+
+        for chunk_size in 1..=EXAMPLE.len() + 1 {
+            let streamed: Vec<SpannedInstruction> =
+                stream_instructions(EXAMPLE, WhitespacePolicy::Forbidden, chunk_size)
+                    .collect::<io::Result<_>>()
+                    .unwrap();
+            assert_eq!(streamed, expected, "chunk size {}", chunk_size);
+        }
+    }
+
+    #[test]
+    fn test_long_words_are_not_kept() {
+        let mut input = vec![b'a'; 100_000];
+        input.extend_from_slice(b"mul(2,3)");
+        input.extend(vec![b'x'; 100_000]);
+        input.extend_from_slice(b"don't()");
+
+        let mut parser = StreamParser::new(
+            &input[..],
+            puzzle_signatures(),
+            WhitespacePolicy::Forbidden,
+            64,
+        );
+        let mut calls = 0;
+        while !parser.eof {
+            parser.read_chunk().unwrap();
+            StreamParser::scan(&mut parser);
+            assert!(parser.buffer.len() <= 64 + "don't".len());
+            calls += parser.pending.drain(..).count();
+        }
+        assert_eq!(calls, 2);
+
+        let expected = parse_with(&input, WhitespacePolicy::Forbidden);
+        let streamed: Vec<SpannedInstruction> =
+            stream_instructions(&input[..], WhitespacePolicy::Forbidden, 7)
+                .collect::<io::Result<_>>()
+                .unwrap();
+        assert_eq!(streamed, expected);
+    }
+
+    #[test]
+    fn test_sum_reader() {
+        let sums: Sums<i64> = sum_reader(EXAMPLE, WhitespacePolicy::Forbidden, 4).unwrap();
+
+        assert_eq!(sums.products, Ok(161));
+        assert_eq!(sums.enabled_products, Ok(48));
+    }
+
+    proptest! {
+        #[test]
+        fn prop_stream_matches_in_memory_parser(
+            pieces in prop::collection::vec(
+                prop::sample::select(vec![
+                    "mul", "do", "don't", "(", ")", ",", "7", "42", "1234", " ", "\n", "x", "?",
+                ]),
+                0..60,
+            ),
+            chunk_size in 1..16usize,
+            whitespace in prop::sample::select(vec![
+                WhitespacePolicy::Forbidden,
+                WhitespacePolicy::SameLine,
+                WhitespacePolicy::Anywhere,
+            ]),
+        ) {
+            let memory = pieces.concat().into_bytes();
+            let expected = parse_with(&memory, whitespace);
+            let streamed: Vec<SpannedInstruction> =
+                stream_instructions(&memory[..], whitespace, chunk_size)
+                    .collect::<io::Result<_>>()
+                    .unwrap();
+            prop_assert_eq!(&streamed, &expected);
+
+            let sums: Sums<i64> = sum_reader(&memory[..], whitespace, chunk_size).unwrap();
+            prop_assert_eq!(sums.products, sum_of_products(&expected));
+            prop_assert_eq!(sums.enabled_products, sum_of_enabled_products(&expected));
+        }
+    }
+}
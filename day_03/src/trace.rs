@@ -0,0 +1,281 @@
+use std::fmt::Write;
+use std::ops::Range;
+
+use crate::{Instruction, OverflowError, SpannedInstruction};
+
+// One recognised instruction of part 2 with the machine state after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub instruction: Instruction,
+    pub span: Range<usize>,
+    // 1-based position of the first byte, counting bytes.
+    pub line: usize,
+    pub column: usize,
+    // For a `mul` whether it counted, for `do` and `don't` the new state.
+    pub enabled: bool,
+    // Only set for `mul`, also when it is disabled.
+    pub product: Option<i64>,
+    pub total: i64,
+}
+
+// Offset of the first byte of every line.
+pub(crate) fn line_starts(memory: &[u8]) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(
+            memory
+                .iter()
+                .enumerate()
+                .filter(|(_, &byte)| byte == b'\n')
+                .map(|(idx, _)| idx + 1),
+        )
+        .collect()
+}
+
+// 1-based line and column of a byte offset, counting bytes.
+pub(crate) fn line_column(line_starts: &[usize], offset: usize) -> (usize, usize) {
+    let line = line_starts.partition_point(|&start| start <= offset);
+    (line, offset - line_starts[line - 1] + 1)
+}
+
+// Replays part 2 over instructions parsed from `memory`, recording every step.
+pub fn trace(
+    memory: &[u8],
+    instructions: &[SpannedInstruction],
+) -> Result<Vec<TraceEntry>, OverflowError> {
+    let line_starts = line_starts(memory);
+
+    let mut entries = Vec::with_capacity(instructions.len());
+    let mut enabled = true;
+    let mut total: i64 = 0;
+    for parsed in instructions {
+        let mut product = None;
+        match parsed.instruction {
+            Instruction::Mul(first, second) => {
+                let value = first as i64 * second as i64;
+                if enabled {
+                    total = total.checked_add(value).ok_or_else(|| OverflowError {
+                        span: parsed.span.clone(),
+                    })?;
+                }
+                product = Some(value);
+            }
+            Instruction::Do => enabled = true,
+            Instruction::Dont => enabled = false,
+        }
+
+        let (line, column) = line_column(&line_starts, parsed.span.start);
+        entries.push(TraceEntry {
+            instruction: parsed.instruction,
+            span: parsed.span.clone(),
+            line,
+            column,
+            enabled,
+            product,
+            total,
+        });
+    }
+    Ok(entries)
+}
+
+// One line per entry, e.g. `1:2 mul(2,4) enabled product=8 total=8`.
+pub fn render_trace(memory: &[u8], entries: &[TraceEntry]) -> String {
+    let mut text = String::new();
+    for entry in entries {
+        let state = if entry.enabled { "enabled" } else { "disabled" };
+        write!(
+            text,
+            "{}:{} {} {}",
+            entry.line,
+            entry.column,
+            String::from_utf8_lossy(&memory[entry.span.clone()]),
+            state
+        )
+        .unwrap();
+        if let Some(product) = entry.product {
+            write!(text, " product={}", product).unwrap();
+        }
+        writeln!(text, " total={}", entry.total).unwrap();
+    }
+    text
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Highlight {
+    EnabledMul,
+    DisabledMul,
+    Do,
+    Dont,
+}
+
+impl Highlight {
+    fn of(entry: &TraceEntry) -> Highlight {
+        match entry.instruction {
+            Instruction::Mul(_, _) if entry.enabled => Highlight::EnabledMul,
+            Instruction::Mul(_, _) => Highlight::DisabledMul,
+            Instruction::Do => Highlight::Do,
+            Instruction::Dont => Highlight::Dont,
+        }
+    }
+
+    fn ansi(self) -> &'static str {
+        match self {
+            Highlight::EnabledMul => "\x1b[1;32m",
+            Highlight::DisabledMul => "\x1b[2;31m",
+            Highlight::Do => "\x1b[1;36m",
+            Highlight::Dont => "\x1b[1;33m",
+        }
+    }
+
+    fn class(self) -> &'static str {
+        match self {
+            Highlight::EnabledMul => "enabled",
+            Highlight::DisabledMul => "disabled",
+            Highlight::Do => "do",
+            Highlight::Dont => "dont",
+        }
+    }
+}
+
+// Splits the memory into plain and highlighted segments. The entries are
+// ordered and never overlap.
+fn segments<'a>(memory: &'a [u8], entries: &[TraceEntry]) -> Vec<(Option<Highlight>, &'a [u8])> {
+    let mut segments = vec![];
+    let mut position = 0;
+    for entry in entries {
+        if position < entry.span.start {
+            segments.push((None, &memory[position..entry.span.start]));
+        }
+        segments.push((Some(Highlight::of(entry)), &memory[entry.span.clone()]));
+        position = entry.span.end;
+    }
+    if position < memory.len() {
+        segments.push((None, &memory[position..]));
+    }
+    segments
+}
+
+// The original memory with the instructions colored for an ANSI terminal:
+// enabled `mul`s green, disabled ones dim red, `do` cyan and `don't` yellow.
+pub fn render_ansi(memory: &[u8], entries: &[TraceEntry]) -> String {
+    let mut text = String::new();
+    for (highlight, bytes) in segments(memory, entries) {
+        let bytes = String::from_utf8_lossy(bytes);
+        match highlight {
+            Some(highlight) => write!(text, "{}{}\x1b[0m", highlight.ansi(), bytes).unwrap(),
+            None => text.push_str(&bytes),
+        }
+    }
+    text
+}
+
+// The same annotation as a standalone HTML page.
+pub fn render_html(memory: &[u8], entries: &[TraceEntry]) -> String {
+    let mut body = String::new();
+    for (highlight, bytes) in segments(memory, entries) {
+        let bytes = escape_html(&String::from_utf8_lossy(bytes));
+        match highlight {
+            Some(highlight) => write!(
+                body,
+                "<span class=\"{}\">{}</span>",
+                highlight.class(),
+                bytes
+            )
+            .unwrap(),
+            None => body.push_str(&bytes),
+        }
+    }
+
+    format!(
+        "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>Corrupted memory</title>
+<style>
+pre {{ white-space: pre-wrap; word-break: break-all; }}
+.enabled {{ color: #1a7f37; font-weight: bold; }}
+.disabled {{ color: #cf222e; opacity: 0.6; text-decoration: line-through; }}
+.do {{ color: #0969da; font-weight: bold; }}
+.dont {{ color: #9a6700; font-weight: bold; }}
+</style>
+</head>
+<body>
+<pre>{}</pre>
+</body>
+</html>
+",
+        body
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const MEMORY: &[u8] = b"xmul(2,4)don't()\n_mul(5,5)<do()mul(8,5)";
+
+    fn example() -> Vec<TraceEntry> {
+        trace(MEMORY, &parse(MEMORY)).unwrap()
+    }
+
+    #[test]
+    fn test_trace() {
+        let entries = example();
+
+        let positions: Vec<(usize, usize)> = entries
+            .iter()
+            .map(|entry| (entry.line, entry.column))
+            .collect();
+        assert_eq!(positions, vec![(1, 2), (1, 10), (2, 2), (2, 11), (2, 15)]);
+        let states: Vec<(bool, Option<i64>, i64)> = entries
+            .iter()
+            .map(|entry| (entry.enabled, entry.product, entry.total))
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                (true, Some(8), 8),
+                (false, None, 8),
+                (false, Some(25), 8),
+                (true, None, 8),
+                (true, Some(40), 48),
+            ]
+        );
+
+        assert_eq!(
+            render_trace(MEMORY, &entries[..3]),
+            "1:2 mul(2,4) enabled product=8 total=8\n\
+             1:10 don't() disabled total=8\n\
+             2:2 mul(5,5) disabled product=25 total=8\n"
+        );
+    }
+
+    #[test]
+    fn test_render_annotations() {
+        let entries = example();
+
+        let ansi = render_ansi(MEMORY, &entries);
+        assert!(ansi.starts_with("x\x1b[1;32mmul(2,4)\x1b[0m\x1b[1;33mdon't()\x1b[0m\n_"));
+        assert!(ansi.contains("\x1b[2;31mmul(5,5)\x1b[0m<"));
+
+        let html = render_html(MEMORY, &entries);
+        assert!(html.contains(
+            "<span class=\"disabled\">mul(5,5)</span>&lt;<span class=\"do\">do()</span>"
+        ));
+    }
+}
@@ -0,0 +1,294 @@
+use std::fmt;
+use std::ops::Range;
+
+use crate::parser::{parse_calls, Signature, WhitespacePolicy};
+
+// Explicit state of the machine between two instructions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Machine {
+    pub enabled: bool,
+    pub accumulator: i64,
+    pub stack: Vec<i32>,
+    // Number of open `if()` blocks being skipped because their condition was
+    // false, counting blocks nested inside them.
+    pub skip_depth: usize,
+}
+
+impl Machine {
+    pub fn new() -> Machine {
+        Machine {
+            enabled: true,
+            ..Machine::default()
+        }
+    }
+
+    fn pop(&mut self) -> Result<i32, VmErrorKind> {
+        self.stack.pop().ok_or(VmErrorKind::StackUnderflow)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VmErrorKind {
+    DivisionByZero,
+    StackUnderflow,
+    Overflow,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VmError {
+    pub kind: VmErrorKind,
+    pub span: Range<usize>,
+}
+
+impl fmt::Display for VmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.kind {
+            VmErrorKind::DivisionByZero => "division by zero",
+            VmErrorKind::StackUnderflow => "pop from an empty stack",
+            VmErrorKind::Overflow => "accumulator overflowed",
+        };
+        write!(
+            f,
+            "{} at bytes {}..{}",
+            reason, self.span.start, self.span.end
+        )
+    }
+}
+
+impl std::error::Error for VmError {}
+
+pub type Handler = fn(&mut Machine, &[i32]) -> Result<(), VmErrorKind>;
+
+// How an opcode affects skipped `if()` blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Block {
+    None,
+    Open,
+    Close,
+}
+
+#[derive(Debug, Clone)]
+pub struct Opcode {
+    pub signature: Signature,
+    pub block: Block,
+    pub handler: Handler,
+}
+
+// Set of instructions the machine understands. Variations of the puzzle are
+// built by registering more opcodes rather than by changing the parser.
+#[derive(Debug, Clone, Default)]
+pub struct InstructionTable {
+    opcodes: Vec<Opcode>,
+    whitespace: WhitespacePolicy,
+}
+
+impl InstructionTable {
+    pub fn new() -> InstructionTable {
+        InstructionTable::default()
+    }
+
+    pub fn register(&mut self, name: &str, arity: usize, handler: Handler) -> &mut Self {
+        self.register_block(name, arity, Block::None, handler)
+    }
+
+    pub fn register_block(
+        &mut self,
+        name: &str,
+        arity: usize,
+        block: Block,
+        handler: Handler,
+    ) -> &mut Self {
+        self.opcodes.retain(|opcode| opcode.signature.name != name);
+        self.opcodes.push(Opcode {
+            signature: Signature::new(name, arity),
+            block,
+            handler,
+        });
+        self
+    }
+
+    // `mul`, `do` and `don't` as described by the puzzle.
+    pub fn puzzle() -> InstructionTable {
+        let mut table = InstructionTable::new();
+        table
+            .register("mul", 2, |machine, args| {
+                accumulate(machine, args[0] as i64 * args[1] as i64)
+            })
+            .register("do", 0, |machine, _| {
+                machine.enabled = true;
+                Ok(())
+            })
+            .register("don't", 0, |machine, _| {
+                machine.enabled = false;
+                Ok(())
+            });
+        table
+    }
+
+    // The puzzle instructions plus:
+    // - `add(a,b)`, `sub(a,b)` and `div(a,b)` accumulate like `mul`,
+    // - `push(a)` pushes a value and `pop()` adds the top value to the total,
+    // - `if()` pops a value and skips everything up to the matching `end()`
+    //   when it is zero.
+    pub fn extended() -> InstructionTable {
+        let mut table = InstructionTable::puzzle();
+        table
+            .register("add", 2, |machine, args| {
+                accumulate(machine, args[0] as i64 + args[1] as i64)
+            })
+            .register("sub", 2, |machine, args| {
+                accumulate(machine, args[0] as i64 - args[1] as i64)
+            })
+            .register("div", 2, |machine, args| {
+                if args[1] == 0 {
+                    return Err(VmErrorKind::DivisionByZero);
+                }
+                accumulate(machine, (args[0] / args[1]) as i64)
+            })
+            .register("push", 1, |machine, args| {
+                machine.stack.push(args[0]);
+                Ok(())
+            })
+            .register("pop", 0, |machine, _| {
+                let value = machine.pop()?;
+                accumulate(machine, value as i64)
+            })
+            .register_block("if", 0, Block::Open, |machine, _| {
+                if machine.pop()? == 0 {
+                    machine.skip_depth = 1;
+                }
+                Ok(())
+            })
+            .register_block("end", 0, Block::Close, |_, _| Ok(()));
+        table
+    }
+
+    // Whitespace allowed inside instructions, none by default.
+    pub fn set_whitespace(&mut self, whitespace: WhitespacePolicy) -> &mut Self {
+        self.whitespace = whitespace;
+        self
+    }
+
+    pub fn signatures(&self) -> Vec<Signature> {
+        self.opcodes
+            .iter()
+            .map(|opcode| opcode.signature.clone())
+            .collect()
+    }
+
+    pub fn get(&self, idx: usize) -> Option<&Opcode> {
+        self.opcodes.get(idx)
+    }
+
+    // Parses the memory with this table and runs it on a fresh machine.
+    pub fn run(&self, memory: &[u8]) -> Result<Machine, VmError> {
+        let mut machine = Machine::new();
+        self.execute(&mut machine, memory)?;
+        Ok(machine)
+    }
+
+    // Runs the memory on an existing machine, so that its state carries over
+    // from earlier memory.
+    pub fn execute(&self, machine: &mut Machine, memory: &[u8]) -> Result<(), VmError> {
+        for call in parse_calls(memory, &self.signatures(), self.whitespace) {
+            let opcode = &self.opcodes[call.signature];
+
+            if machine.skip_depth > 0 {
+                match opcode.block {
+                    Block::Open => machine.skip_depth += 1,
+                    Block::Close => machine.skip_depth -= 1,
+                    Block::None => {}
+                }
+                continue;
+            }
+
+            (opcode.handler)(machine, &call.args).map_err(|kind| VmError {
+                kind,
+                span: call.span.clone(),
+            })?;
+        }
+        Ok(())
+    }
+}
+
+// Arithmetic only counts while the machine is enabled.
+fn accumulate(machine: &mut Machine, value: i64) -> Result<(), VmErrorKind> {
+    if machine.enabled {
+        machine.accumulator = machine
+            .accumulator
+            .checked_add(value)
+            .ok_or(VmErrorKind::Overflow)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &[u8] =
+        b"xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+
+    #[test]
+    fn test_puzzle_table_matches_part_2() {
+        let machine = InstructionTable::puzzle().run(EXAMPLE).unwrap();
+
+        assert_eq!(machine.accumulator, 48);
+        assert!(machine.enabled);
+    }
+
+    #[test]
+    fn test_registering_opcodes() {
+        // Part 1 is the puzzle without `do` and `don't`.
+        let mut table = InstructionTable::new();
+        table.register("mul", 2, |machine, args| {
+            machine.accumulator += (args[0] * args[1]) as i64;
+            Ok(())
+        });
+
+        assert_eq!(table.run(EXAMPLE).unwrap().accumulator, 161);
+    }
+
+    #[test]
+    fn test_extended_table() {
+        let table = InstructionTable::extended();
+
+        let machine = table
+            .run(b"add(1,2)#sub(10,4)?div(9,2)push(5)push(7)pop()")
+            .unwrap();
+        assert_eq!(machine.accumulator, 3 + 6 + 4 + 7);
+        assert_eq!(machine.stack, vec![5]);
+
+        let machine = table
+            .run(b"push(0)if()mul(2,2)push(1)if()mul(3,3)end()end()push(1)if()mul(4,4)end()")
+            .unwrap();
+        assert_eq!(machine.accumulator, 16);
+    }
+
+    #[test]
+    fn test_extended_table_errors() {
+        let table = InstructionTable::extended();
+
+        assert_eq!(
+            table.run(b"mul(1,1)div(4,0)").unwrap_err(),
+            VmError {
+                kind: VmErrorKind::DivisionByZero,
+                span: 8..16,
+            }
+        );
+        assert_eq!(
+            table.run(b"pop()").unwrap_err().kind,
+            VmErrorKind::StackUnderflow
+        );
+
+        let mut machine = Machine::new();
+        machine.accumulator = i64::MAX - 1;
+        assert_eq!(
+            table.execute(&mut machine, b"mul(1,1)mul(1,1)"),
+            Err(VmError {
+                kind: VmErrorKind::Overflow,
+                span: 8..16,
+            })
+        );
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{Direction, Grid, Match};
+
+// One word of the dictionary found in the grid, with the position of its last
+// letter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WordMatch {
+    pub word: usize,
+    pub at: Match,
+    pub end: (usize, usize),
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    next: HashMap<char, usize>,
+    fail: usize,
+    // Words ending exactly at this node.
+    words: Vec<usize>,
+    // Closest node on the failure chain that ends a word.
+    output: Option<usize>,
+}
+
+// Aho-Corasick automaton over a list of words. Every line of the grid in
+// every direction is fed through it once, so a search takes time linear in
+// the grid size plus the number of matches, however many words there are.
+#[derive(Debug)]
+pub struct Dictionary {
+    nodes: Vec<Node>,
+    // Length of every word in letters.
+    lengths: Vec<usize>,
+}
+
+impl Dictionary {
+    // Empty words are kept so that indices match, but never found.
+    pub fn new<S: AsRef<str>>(words: &[S]) -> Dictionary {
+        let mut nodes = vec![Node::default()];
+        let mut lengths = Vec::with_capacity(words.len());
+
+        for (idx, word) in words.iter().enumerate() {
+            let word = word.as_ref();
+            lengths.push(word.chars().count());
+            if word.is_empty() {
+                continue;
+            }
+            let mut node = 0;
+            for letter in word.chars() {
+                node = match nodes[node].next.get(&letter) {
+                    Some(&next) => next,
+                    None => {
+                        nodes.push(Node::default());
+                        let next = nodes.len() - 1;
+                        nodes[node].next.insert(letter, next);
+                        next
+                    }
+                };
+            }
+            nodes[node].words.push(idx);
+        }
+
+        // Failure links in breadth-first order, so that the links of shorter
+        // prefixes are known first.
+        let mut queue: VecDeque<usize> = nodes[0].next.values().copied().collect();
+        while let Some(node) = queue.pop_front() {
+            let children: Vec<(char, usize)> = nodes[node]
+                .next
+                .iter()
+                .map(|(&letter, &child)| (letter, child))
+                .collect();
+            for (letter, child) in children {
+                let mut fail = nodes[node].fail;
+                let fail = loop {
+                    if let Some(&next) = nodes[fail].next.get(&letter) {
+                        break next;
+                    }
+                    if fail == 0 {
+                        break 0;
+                    }
+                    fail = nodes[fail].fail;
+                };
+                nodes[child].fail = fail;
+                nodes[child].output = if nodes[fail].words.is_empty() {
+                    nodes[fail].output
+                } else {
+                    Some(fail)
+                };
+                queue.push_back(child);
+            }
+        }
+
+        Dictionary { nodes, lengths }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lengths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lengths.is_empty()
+    }
+
+    fn step(&self, mut node: usize, letter: char) -> usize {
+        loop {
+            if let Some(&next) = self.nodes[node].next.get(&letter) {
+                return next;
+            }
+            if node == 0 {
+                return 0;
+            }
+            node = self.nodes[node].fail;
+        }
+    }
+
+    // Every occurrence of every word in all eight directions.
+    pub fn search(&self, grid: &Grid) -> Vec<WordMatch> {
+        self.search_along(grid, &Direction::ALL)
+    }
+
+    // Every occurrence of every word along the given directions. A direction
+    // that stays in place never finds anything.
+    pub fn search_along(&self, grid: &Grid, directions: &[Direction]) -> Vec<WordMatch> {
+        let rows = grid.rows() as isize;
+        let cols = grid.cols() as isize;
+        let on_grid = |row: isize, col: isize| (0..rows).contains(&row) && (0..cols).contains(&col);
+
+        let mut matches = vec![];
+        for &direction in directions {
+            if direction == Direction::new(0, 0) {
+                continue;
+            }
+            let Direction { dr, dc } = direction;
+            // Every line starts at a cell whose predecessor is off the grid.
+            for row in 0..rows {
+                for col in 0..cols {
+                    if on_grid(row - dr, col - dc) {
+                        continue;
+                    }
+
+                    let mut node = 0;
+                    let (mut r, mut c) = (row, col);
+                    let mut line: Vec<(usize, usize)> = vec![];
+                    while on_grid(r, c) {
+                        let (cell_row, cell_col) = (r as usize, c as usize);
+                        line.push((cell_row, cell_col));
+                        node = self.step(node, grid.get(cell_row, cell_col).unwrap());
+                        self.collect(node, &line, direction, &mut matches);
+                        r += dr;
+                        c += dc;
+                    }
+                }
+            }
+        }
+        matches
+    }
+
+    // Reports every word ending at `node`, the last cell of `line`.
+    fn collect(
+        &self,
+        node: usize,
+        line: &[(usize, usize)],
+        direction: Direction,
+        matches: &mut Vec<WordMatch>,
+    ) {
+        let end = line[line.len() - 1];
+        let mut current = Some(node);
+        if self.nodes[node].words.is_empty() {
+            current = self.nodes[node].output;
+        }
+        while let Some(found) = current {
+            for &word in &self.nodes[found].words {
+                matches.push(WordMatch {
+                    word,
+                    at: direction.at(line[line.len() - self.lengths[word]]),
+                    end,
+                });
+            }
+            current = self.nodes[found].output;
+        }
+    }
+
+    // Number of matches of every word.
+    pub fn counts(&self, matches: &[WordMatch]) -> Vec<usize> {
+        let mut counts = vec![0; self.len()];
+        for found in matches {
+            counts[found.word] += 1;
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{example_grid, find_all_words, find_words_along, Edges};
+
+    #[test]
+    fn test_dictionary_counts() {
+        let dictionary = Dictionary::new(&["XMAS", "MAS", "AS", "", "SAMX"]);
+        let matches = dictionary.search(&example_grid());
+        let counts = dictionary.counts(&matches);
+
+        assert_eq!(counts[0], 18);
+        assert_eq!(counts[0], counts[4]);
+        assert_eq!(counts[3], 0);
+        for (idx, word) in ["XMAS", "MAS", "AS"].iter().enumerate() {
+            assert_eq!(counts[idx], find_all_words(word, &example_grid()).len());
+        }
+    }
+
+    #[test]
+    fn test_dictionary_matches() {
+        let grid = Grid::from_rows(&["XMAS.", "....."]).unwrap();
+        let dictionary = Dictionary::new(&["MAS", "XMAS"]);
+
+        let mut matches = dictionary.search(&grid);
+        matches.sort_by_key(|found| found.word);
+        assert_eq!(
+            matches,
+            vec![
+                WordMatch {
+                    word: 0,
+                    at: Direction::RIGHT.at((0, 1)),
+                    end: (0, 3),
+                },
+                WordMatch {
+                    word: 1,
+                    at: Direction::RIGHT.at((0, 0)),
+                    end: (0, 3),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dictionary_along_knight_moves() {
+        let dictionary = Dictionary::new(&["XMAS", "SAMX"]);
+
+        let mut found: Vec<Match> = dictionary
+            .search_along(&example_grid(), &Direction::KNIGHT_MOVES)
+            .into_iter()
+            .map(|found| found.at)
+            .collect();
+        let mut expected = find_words_along(
+            "XMAS",
+            &example_grid(),
+            Edges::Bounded,
+            &Direction::KNIGHT_MOVES,
+        );
+        expected.extend(find_words_along(
+            "SAMX",
+            &example_grid(),
+            Edges::Bounded,
+            &Direction::KNIGHT_MOVES,
+        ));
+        found.sort();
+        expected.sort();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn test_dictionary_matches_directions() {
+        let dictionary = Dictionary::new(&["XMAS"]);
+
+        let mut found: Vec<Match> = dictionary
+            .search(&example_grid())
+            .into_iter()
+            .map(|found| found.at)
+            .collect();
+        let mut expected = find_all_words("XMAS", &example_grid());
+        found.sort();
+        expected.sort();
+        assert_eq!(found, expected);
+    }
+}
@@ -0,0 +1,149 @@
+use std::fmt;
+
+use crate::{Edges, Grid};
+
+// Step from one letter of a word to the next, in rows and columns. Any step
+// can be used, e.g. `Direction::new(0, 2)` to skip every other letter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Direction {
+    pub dr: isize,
+    pub dc: isize,
+}
+
+impl Direction {
+    pub const RIGHT: Direction = Direction::new(0, 1);
+    pub const LEFT: Direction = Direction::new(0, -1);
+    pub const DOWN: Direction = Direction::new(1, 0);
+    pub const UP: Direction = Direction::new(-1, 0);
+    pub const DIAGONAL_RIGHT_DOWN: Direction = Direction::new(1, 1);
+    pub const DIAGONAL_RIGHT_UP: Direction = Direction::new(-1, 1);
+    pub const DIAGONAL_LEFT_DOWN: Direction = Direction::new(1, -1);
+    pub const DIAGONAL_LEFT_UP: Direction = Direction::new(-1, -1);
+
+    // The eight directions of the puzzle, in the order words are searched.
+    pub const ALL: [Direction; 8] = [
+        Direction::RIGHT,
+        Direction::LEFT,
+        Direction::DOWN,
+        Direction::UP,
+        Direction::DIAGONAL_RIGHT_DOWN,
+        Direction::DIAGONAL_RIGHT_UP,
+        Direction::DIAGONAL_LEFT_DOWN,
+        Direction::DIAGONAL_LEFT_UP,
+    ];
+
+    pub const KNIGHT_MOVES: [Direction; 8] = [
+        Direction::new(-2, 1),
+        Direction::new(-1, 2),
+        Direction::new(1, 2),
+        Direction::new(2, 1),
+        Direction::new(2, -1),
+        Direction::new(1, -2),
+        Direction::new(-1, -2),
+        Direction::new(-2, -1),
+    ];
+
+    pub const fn new(dr: isize, dc: isize) -> Direction {
+        Direction { dr, dc }
+    }
+
+    pub fn all() -> impl Iterator<Item = Direction> {
+        Direction::ALL.into_iter()
+    }
+
+    pub fn reversed(self) -> Direction {
+        Direction::new(-self.dr, -self.dc)
+    }
+
+    pub fn at(self, start: (usize, usize)) -> Match {
+        Match {
+            start,
+            direction: self,
+        }
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match (self.dr, self.dc) {
+            (0, 1) => "right",
+            (0, -1) => "left",
+            (1, 0) => "down",
+            (-1, 0) => "up",
+            (1, 1) => "right-down",
+            (-1, 1) => "right-up",
+            (1, -1) => "left-down",
+            (-1, -1) => "left-up",
+            (dr, dc) => return write!(f, "({}, {})", dr, dc),
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// A word found in the grid: the position of its first letter and the step to
+// each next one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Match {
+    pub start: (usize, usize),
+    pub direction: Direction,
+}
+
+impl Match {
+    // Position of the letter `i` steps from the start, if there is one.
+    pub fn cell(&self, i: usize, grid: &Grid, edges: Edges) -> Option<(usize, usize)> {
+        let i = i as isize;
+        grid.offset(
+            self.start,
+            (self.direction.dr * i, self.direction.dc * i),
+            edges,
+        )
+    }
+
+    // Positions of a word of `len` letters, if it may be read under the
+    // given edges.
+    pub fn cells(&self, len: usize, grid: &Grid, edges: Edges) -> Option<Vec<(usize, usize)>> {
+        if len == 0 || !grid.fits(len, (self.direction.dr, self.direction.dc), edges) {
+            return None;
+        }
+        (0..len).map(|i| self.cell(i, grid, edges)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directions() {
+        assert_eq!(Direction::all().count(), 8);
+        assert!(Direction::all().all(|direction| Direction::ALL.contains(&direction.reversed())));
+        assert_eq!(Direction::DIAGONAL_LEFT_UP.to_string(), "left-up");
+        assert_eq!(Direction::new(2, -1).to_string(), "(2, -1)");
+    }
+
+    #[test]
+    fn test_match_cells() {
+        let grid = Grid::from_rows(&["XMAS", "....", "...."]).unwrap();
+
+        let found = Direction::new(1, 2).at((0, 0));
+        assert_eq!(
+            found.cells(2, &grid, Edges::Bounded),
+            Some(vec![(0, 0), (1, 2)])
+        );
+        assert_eq!(found.cells(3, &grid, Edges::Bounded), None);
+        assert_eq!(
+            found.cells(3, &grid, Edges::Wrap),
+            Some(vec![(0, 0), (1, 2), (2, 0)])
+        );
+        assert_eq!(
+            Direction::new(0, 0)
+                .at((0, 0))
+                .cells(2, &grid, Edges::Bounded),
+            None
+        );
+        assert_eq!(
+            Direction::RIGHT.at((0, 0)).cells(0, &grid, Edges::Bounded),
+            None
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use std::fmt;
+use std::io;
+
+use crate::read_words_data;
+
+// Rows of different lengths can not form a grid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RaggedRowError {
+    // 0-based index of the first row whose length differs from the first.
+    pub row: usize,
+    pub len: usize,
+    pub expected: usize,
+}
+
+impl fmt::Display for RaggedRowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "row {} has {} letters, expected {} like the first row",
+            self.row + 1,
+            self.len,
+            self.expected
+        )
+    }
+}
+
+impl std::error::Error for RaggedRowError {}
+
+// What happens to a word that runs into an edge of the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Edges {
+    // Words stop at the edges, as in the puzzle.
+    #[default]
+    Bounded,
+    // The grid is a torus: words continue from the opposite edge. A word may
+    // not visit a cell twice, so words longer than the loop they run along
+    // are never found.
+    Wrap,
+    // Like `Wrap`, but long words go round the loop again and reuse cells.
+    WrapReuse,
+}
+
+// Rectangular letter grid stored row by row, so that every cell is a single
+// index. A letter is one Unicode code point: `é` written as `e` followed by a
+// combining accent takes two cells, like it takes two positions in the
+// string. Grapheme clusters would need a segmentation table the puzzle does
+// not justify.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid {
+    rows: usize,
+    cols: usize,
+    letters: Vec<char>,
+}
+
+impl Grid {
+    pub fn from_rows<S: AsRef<str>>(rows: &[S]) -> Result<Grid, RaggedRowError> {
+        let cols = rows.first().map_or(0, |row| row.as_ref().chars().count());
+        let mut letters = Vec::with_capacity(rows.len() * cols);
+
+        for (idx, row) in rows.iter().enumerate() {
+            let before = letters.len();
+            letters.extend(row.as_ref().chars());
+            let len = letters.len() - before;
+            if len != cols {
+                return Err(RaggedRowError {
+                    row: idx,
+                    len,
+                    expected: cols,
+                });
+            }
+        }
+
+        Ok(Grid {
+            rows: rows.len(),
+            cols,
+            letters,
+        })
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<char> {
+        if row < self.rows && col < self.cols {
+            Some(self.letters[row * self.cols + col])
+        } else {
+            None
+        }
+    }
+
+    pub fn row(&self, row: usize) -> &[char] {
+        &self.letters[row * self.cols..(row + 1) * self.cols]
+    }
+
+    // Position `offset` cells away from `origin`, if there is one under the
+    // given edges. `origin` itself must be on the grid.
+    pub fn offset(
+        &self,
+        origin: (usize, usize),
+        (dr, dc): (isize, isize),
+        edges: Edges,
+    ) -> Option<(usize, usize)> {
+        if origin.0 >= self.rows || origin.1 >= self.cols {
+            return None;
+        }
+        let row = origin.0 as isize + dr;
+        let col = origin.1 as isize + dc;
+        match edges {
+            Edges::Bounded => {
+                let on_grid = (0..self.rows as isize).contains(&row)
+                    && (0..self.cols as isize).contains(&col);
+                on_grid.then_some((row as usize, col as usize))
+            }
+            Edges::Wrap | Edges::WrapReuse => Some((
+                row.rem_euclid(self.rows as isize) as usize,
+                col.rem_euclid(self.cols as isize) as usize,
+            )),
+        }
+    }
+
+    // Number of distinct cells visited by repeating `step` on the torus
+    // before coming back to the start.
+    pub fn loop_len(&self, (dr, dc): (isize, isize)) -> usize {
+        if self.letters.is_empty() {
+            return 0;
+        }
+        let period = |size: usize, delta: isize| size / gcd(size, delta.unsigned_abs() % size);
+        lcm(period(self.rows, dr), period(self.cols, dc))
+    }
+
+    // Whether a word of `len` letters may run along `step` under the given
+    // edges. Bounded edges are checked cell by cell by `offset` instead, apart
+    // from a step that stays in place.
+    pub fn fits(&self, len: usize, step: (isize, isize), edges: Edges) -> bool {
+        match edges {
+            Edges::Bounded => step != (0, 0) || len <= 1,
+            Edges::Wrap => len <= self.loop_len(step),
+            Edges::WrapReuse => !self.letters.is_empty(),
+        }
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn lcm(a: usize, b: usize) -> usize {
+    a / gcd(a, b) * b
+}
+
+// Reads a grid file, skipping empty lines. Ragged rows are reported as
+// invalid data.
+pub fn read_grid(file_path: &str) -> io::Result<Grid> {
+    let rows = read_words_data(file_path)?;
+    Grid::from_rows(&rows).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid_indexing() {
+        let grid = Grid::from_rows(&["XMAS", "ÄÖÜß"]).unwrap();
+
+        assert_eq!((grid.rows(), grid.cols()), (2, 4));
+        assert_eq!(grid.get(0, 3), Some('S'));
+        assert_eq!(grid.get(1, 3), Some('ß'));
+        assert_eq!(grid.get(1, 4), None);
+        assert_eq!(grid.get(2, 0), None);
+        assert_eq!(grid.row(1), &['Ä', 'Ö', 'Ü', 'ß']);
+    }
+
+    #[test]
+    fn test_offset_on_a_torus() {
+        let grid = Grid::from_rows(&["XMAS", "SAMX", "MASX"]).unwrap();
+
+        assert_eq!(grid.offset((0, 3), (0, 1), Edges::Bounded), None);
+        assert_eq!(grid.offset((0, 3), (0, 1), Edges::Wrap), Some((0, 0)));
+        assert_eq!(grid.offset((0, 0), (-1, -1), Edges::Wrap), Some((2, 3)));
+        assert_eq!(grid.offset((1, 1), (-4, 9), Edges::WrapReuse), Some((0, 2)));
+        assert_eq!(grid.offset((3, 0), (0, 0), Edges::Wrap), None);
+
+        assert_eq!(grid.loop_len((0, 1)), 4);
+        assert_eq!(grid.loop_len((-1, 0)), 3);
+        assert_eq!(grid.loop_len((1, 1)), 12);
+        assert_eq!(grid.loop_len((0, 2)), 2);
+        assert!(grid.fits(12, (1, -1), Edges::Wrap));
+        assert!(!grid.fits(5, (0, 1), Edges::Wrap));
+        assert!(grid.fits(5, (0, 1), Edges::WrapReuse));
+    }
+
+    #[test]
+    fn test_ragged_rows() {
+        let err = Grid::from_rows(&["XMAS", "XMA", "XMAS"]).unwrap_err();
+
+        assert_eq!(
+            err,
+            RaggedRowError {
+                row: 1,
+                len: 3,
+                expected: 4,
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "row 2 has 3 letters, expected 4 like the first row"
+        );
+    }
+}
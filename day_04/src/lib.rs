@@ -0,0 +1,358 @@
+use std::fs::File;
+use std::io::{self, BufRead};
+use std::path::Path;
+
+pub mod dictionary;
+pub mod direction;
+pub mod grid;
+pub mod pattern;
+pub mod render;
+pub mod stencil;
+
+pub use dictionary::{Dictionary, WordMatch};
+pub use direction::{Direction, Match};
+pub use grid::{read_grid, Edges, Grid, RaggedRowError};
+pub use pattern::{find_pattern, Pattern, PatternMatch};
+pub use render::{Background, Highlights};
+pub use stencil::{Cell, Stencil, StencilMatch, Transform};
+
+pub const WORD_PART_1: &str = "XMAS";
+pub const WORD_PART_2: &str = "MAS";
+
+pub fn read_words_data(file_path: &str) -> io::Result<Vec<String>> {
+    let path = Path::new(file_path);
+    let file = File::open(path)?;
+    let reader = io::BufReader::new(file);
+
+    let mut words = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if !line.is_empty() {
+            words.push(line);
+        }
+    }
+    Ok(words)
+}
+
+pub fn find_all_words(word: &str, grid: &Grid) -> Vec<Match> {
+    find_all_words_with(word, grid, Edges::Bounded)
+}
+
+pub fn find_all_words_with(word: &str, grid: &Grid, edges: Edges) -> Vec<Match> {
+    find_words_along(word, grid, edges, &Direction::ALL)
+}
+
+// Every match of the word along the given directions, by start and then in
+// the order of the directions.
+pub fn find_words_along(
+    word: &str,
+    grid: &Grid,
+    edges: Edges,
+    directions: &[Direction],
+) -> Vec<Match> {
+    let len = word.chars().count();
+    let mut result: Vec<Match> = Vec::new();
+    for row in 0..grid.rows() {
+        for col in 0..grid.cols() {
+            for direction in directions {
+                let found = direction.at((row, col));
+                if let Some(w) = get_word_with(len, &found, grid, edges) {
+                    if w == word {
+                        result.push(found);
+                    }
+                }
+            }
+        }
+    }
+    result
+}
+
+// Centers of every X of the word, which is read along both diagonals in
+// either direction. Only odd-length words cross at a letter.
+pub fn find_crossed_words(word: &str, grid: &Grid) -> Vec<(usize, usize)> {
+    find_crossed_words_with(word, grid, Edges::Bounded)
+}
+
+pub fn find_crossed_words_with(word: &str, grid: &Grid, edges: Edges) -> Vec<(usize, usize)> {
+    let stencil = match Stencil::x_of(word) {
+        Some(stencil) => stencil,
+        None => return vec![],
+    };
+    let half_len = stencil.height() as isize / 2;
+
+    let mut result: Vec<(usize, usize)> = stencil
+        .search_with(grid, &Transform::ROTATIONS, edges)
+        .into_iter()
+        .filter_map(|found| grid.offset(found.origin, (half_len, half_len), edges))
+        .collect();
+    result.sort();
+    result.dedup();
+    result
+}
+
+// Reads `len` letters from the start of the match, if they all fit.
+pub fn get_word(len: usize, found: &Match, grid: &Grid) -> Option<String> {
+    get_word_with(len, found, grid, Edges::Bounded)
+}
+
+// Like `get_word`, reading across the edges as they allow.
+pub fn get_word_with(len: usize, found: &Match, grid: &Grid, edges: Edges) -> Option<String> {
+    found
+        .cells(len, grid, edges)?
+        .into_iter()
+        .map(|(row, col)| grid.get(row, col))
+        .collect()
+}
+
+pub fn get_words_crossed_at_position(
+    len: usize,
+    row: usize,
+    col: usize,
+    grid: &Grid,
+) -> Vec<String> {
+    let mut words = vec![];
+    if len.is_multiple_of(2) {
+        return words;
+    }
+
+    let half_len = len / 2;
+    let max_row = grid.rows();
+    let max_col = grid.cols();
+
+    // Diagonal
+    if col >= half_len && col + half_len < max_col && row >= half_len && row + half_len < max_row {
+        for found in [
+            Direction::DIAGONAL_RIGHT_DOWN.at((row - half_len, col - half_len)),
+            Direction::DIAGONAL_LEFT_UP.at((row + half_len, col + half_len)),
+            Direction::DIAGONAL_LEFT_DOWN.at((row - half_len, col + half_len)),
+            Direction::DIAGONAL_RIGHT_UP.at((row + half_len, col - half_len)),
+        ] {
+            if let Some(word) = get_word(len, &found, grid) {
+                words.push(word);
+            }
+        }
+    }
+
+    words
+}
+
+// The example grid of the puzzle, shared by the tests of every module.
+#[cfg(test)]
+pub(crate) fn example_grid() -> Grid {
+    Grid::from_rows(&[
+        "MMMSXXMASM",
+        "MSAMXMSMSA",
+        "AMXSXMAAMM",
+        "MSAMASMSMX",
+        "XMASAMXAMM",
+        "XXAMMXXAMA",
+        "SMSMSASXSS",
+        "SAXAMASAAA",
+        "MAMMMXMMMM",
+        "MXMXAXMASX",
+    ])
+    .unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        find_all_words_with, find_crossed_words_with, find_words_along,
+        get_words_crossed_at_position, Direction, Edges, Grid,
+    };
+
+    #[test]
+    fn test_find_word_right() {
+        let words_data_1: Vec<String> = vec![
+            "XMAS.".to_string(),
+            ".....".to_string(),
+            ".....".to_string(),
+            ".....".to_string(),
+        ];
+        let words_data_2: Vec<String> = vec![
+            ".XMAS".to_string(),
+            ".....".to_string(),
+            ".....".to_string(),
+            ".....".to_string(),
+        ];
+        let word = "XMAS";
+        let result_1 = super::find_all_words(word, &Grid::from_rows(&words_data_1).unwrap());
+        let result_2 = super::find_all_words(word, &Grid::from_rows(&words_data_2).unwrap());
+
+        assert_eq!(result_1, vec![Direction::RIGHT.at((0, 0))]);
+        assert_eq!(result_2, vec![Direction::RIGHT.at((0, 1))]);
+    }
+
+    #[test]
+    fn test_find_word_left() {
+        let words_data_1: Vec<String> = vec![
+            "SAMX.".to_string(),
+            ".....".to_string(),
+            ".....".to_string(),
+            ".....".to_string(),
+        ];
+        let words_data_2: Vec<String> = vec![
+            ".SAMX".to_string(),
+            ".....".to_string(),
+            ".....".to_string(),
+            ".....".to_string(),
+        ];
+        let word = "XMAS";
+        let result_1 = super::find_all_words(word, &Grid::from_rows(&words_data_1).unwrap());
+        let result_2 = super::find_all_words(word, &Grid::from_rows(&words_data_2).unwrap());
+
+        assert_eq!(result_1, vec![Direction::LEFT.at((0, 3))]);
+        assert_eq!(result_2, vec![Direction::LEFT.at((0, 4))]);
+    }
+
+    #[test]
+    fn test_find_word_down() {
+        let words_data_1: Vec<String> = vec![
+            "X....".to_string(),
+            "M....".to_string(),
+            "A....".to_string(),
+            "S....".to_string(),
+            ".....".to_string(),
+        ];
+        let words_data_2: Vec<String> = vec![
+            ".....".to_string(),
+            "X....".to_string(),
+            "M....".to_string(),
+            "A....".to_string(),
+            "S....".to_string(),
+        ];
+        let word = "XMAS";
+        let result_1 = super::find_all_words(word, &Grid::from_rows(&words_data_1).unwrap());
+        let result_2 = super::find_all_words(word, &Grid::from_rows(&words_data_2).unwrap());
+
+        assert_eq!(result_1, vec![Direction::DOWN.at((0, 0))]);
+        assert_eq!(result_2, vec![Direction::DOWN.at((1, 0))]);
+    }
+
+    #[test]
+    fn test_find_word_up() {
+        let words_data_1: Vec<String> = vec![
+            "S....".to_string(),
+            "A....".to_string(),
+            "M....".to_string(),
+            "X....".to_string(),
+            ".....".to_string(),
+        ];
+        let words_data_2: Vec<String> = vec![
+            ".....".to_string(),
+            "S....".to_string(),
+            "A....".to_string(),
+            "M....".to_string(),
+            "X....".to_string(),
+        ];
+        let word = "XMAS";
+        let result_1 = super::find_all_words(word, &Grid::from_rows(&words_data_1).unwrap());
+        let result_2 = super::find_all_words(word, &Grid::from_rows(&words_data_2).unwrap());
+
+        assert_eq!(result_1, vec![Direction::UP.at((3, 0))]);
+        assert_eq!(result_2, vec![Direction::UP.at((4, 0))]);
+    }
+
+    #[test]
+    fn test_find_crossed_words_uses_word() {
+        let words_data: Vec<String> = vec![
+            "A.A..".to_string(),
+            ".B...".to_string(),
+            "C.C..".to_string(),
+            ".....".to_string(),
+        ];
+        let grid = Grid::from_rows(&words_data).unwrap();
+
+        assert_eq!(super::find_crossed_words("ABC", &grid), vec![(1, 1)]);
+        assert_eq!(super::find_crossed_words("MAS", &grid), vec![]);
+    }
+
+    #[test]
+    fn test_get_words_crossed_at_position() {
+        let words_data_1: Vec<String> = vec![
+            "XMAS.".to_string(),
+            ".....".to_string(),
+            ".....".to_string(),
+            ".....".to_string(),
+        ];
+        let res = get_words_crossed_at_position(3, 0, 0, &Grid::from_rows(&words_data_1).unwrap());
+        let expected_res: Vec<String> = vec![];
+
+        assert_eq!(res, expected_res);
+    }
+
+    #[test]
+    fn test_find_word_unicode() {
+        let words_data: Vec<String> = vec!["ÄXMASÖ".to_string(), "ßSAMXü".to_string()];
+        let grid = Grid::from_rows(&words_data).unwrap();
+
+        assert_eq!(
+            super::find_all_words("XMAS", &grid),
+            vec![Direction::RIGHT.at((0, 1)), Direction::LEFT.at((1, 4))]
+        );
+        assert_eq!(
+            super::find_all_words("XÖ", &grid),
+            vec![Direction::DIAGONAL_RIGHT_UP.at((1, 4))]
+        );
+        assert_eq!(
+            super::find_all_words("SÖ", &grid),
+            vec![Direction::RIGHT.at((0, 4))]
+        );
+        assert_eq!(super::find_all_words("", &grid), vec![]);
+    }
+
+    #[test]
+    fn test_find_word_wrapped() {
+        let grid = Grid::from_rows(&["ASXM", "....", "...."]).unwrap();
+
+        assert_eq!(super::find_all_words("XMAS", &grid), vec![]);
+        assert_eq!(
+            find_all_words_with("XMAS", &grid, Edges::Wrap),
+            vec![Direction::RIGHT.at((0, 2))]
+        );
+    }
+
+    #[test]
+    fn test_find_words_along_custom_steps() {
+        let grid = Grid::from_rows(&["X.....", "..M...", "....A.", "...S.."]).unwrap();
+
+        assert_eq!(
+            find_words_along("XMA", &grid, Edges::Bounded, &Direction::KNIGHT_MOVES),
+            vec![Direction::new(1, 2).at((0, 0))]
+        );
+        let grid = Grid::from_rows(&["X.M.A.S"]).unwrap();
+        assert_eq!(
+            find_words_along("XMAS", &grid, Edges::Bounded, &[Direction::new(0, 2)]),
+            vec![Direction::new(0, 2).at((0, 0))]
+        );
+    }
+
+    #[test]
+    fn test_find_long_word_wrapped() {
+        let grid = Grid::from_rows(&["XMA"]).unwrap();
+
+        assert_eq!(find_all_words_with("XMAX", &grid, Edges::Wrap), vec![]);
+        // On a single row the diagonals loop round the same three cells.
+        assert_eq!(
+            find_all_words_with("XMAX", &grid, Edges::WrapReuse),
+            vec![
+                Direction::RIGHT.at((0, 0)),
+                Direction::DIAGONAL_RIGHT_DOWN.at((0, 0)),
+                Direction::DIAGONAL_RIGHT_UP.at((0, 0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_crossed_words_wrapped() {
+        let grid = Grid::from_rows(&["A..", ".SS", ".MM"]).unwrap();
+
+        assert_eq!(super::find_crossed_words("MAS", &grid), vec![]);
+        assert_eq!(
+            find_crossed_words_with("MAS", &grid, Edges::Wrap),
+            vec![(0, 0)]
+        );
+        let small = Grid::from_rows(&["AM", "SM"]).unwrap();
+        assert_eq!(find_crossed_words_with("MAS", &small, Edges::Wrap), vec![]);
+    }
+}
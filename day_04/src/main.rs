@@ -1,350 +1,153 @@
 use std::env;
-use std::fs::File;
-use std::io::{self, BufRead};
-use std::path::Path;
 
-const WORD_PART_1: &str = "XMAS";
-const WORD_PART_2: &str = "MAS";
-
-#[derive(Debug, PartialEq)]
-enum Direction {
-    Right((usize, usize)),
-    Left((usize, usize)),
-    Down((usize, usize)),
-    Up((usize, usize)),
-    DiagonalRightDown((usize, usize)),
-    DiagonalRightUp((usize, usize)),
-    DiagonalLeftDown((usize, usize)),
-    DiagonalLeftUp((usize, usize)),
-}
+use day_04::{
+    find_all_words_with, find_crossed_words_with, find_pattern, read_grid, read_words_data,
+    Background, Dictionary, Edges, Highlights, Pattern, Stencil, Transform, WORD_PART_1,
+    WORD_PART_2,
+};
 
 fn main() {
     // Get the file path from command-line arguments
     let args: Vec<String> = env::args().collect();
     if args.len() < 2 {
-        eprintln!("Usage: {} <file_path>", args[0]);
+        eprintln!(
+            "Usage: {} <file_path> [--words <file>] [--matches] [--stencil <file>] [--transforms <set>] [--wrap] [--wrap-reuse] [--render <dots|dim>] [--color] [--pattern <word>] [--ignore-case]",
+            args[0]
+        );
+        eprintln!("  --words <file>        count every word of a file, one word per line");
+        eprintln!("  --matches             also list every match of those words or pattern");
+        eprintln!("  --stencil <file>      count every placement of a stencil");
+        eprintln!("  --transforms <set>    stencil transforms: none, rotations or all");
+        eprintln!("  --wrap                wrap around the edges of the grid, except for --words");
+        eprintln!("  --wrap-reuse          like --wrap, letting long words reuse cells");
+        eprintln!("  --render <dots|dim>   print the grid of each part with its matches");
+        eprintln!("  --color               color every match by its direction");
+        eprintln!("  --pattern <word>      count a word where ? is any letter and [AB] A or B");
+        eprintln!("  --ignore-case         match the pattern whatever the case");
         std::process::exit(1);
     }
     let file_path = &args[1];
 
-    let words_data = read_words_data(file_path).expect("Failed to read and parse file");
-
-    println!("===================== Part 1 =====================");
-    let all_words = find_all_words(WORD_PART_1, &words_data);
-    println!("Words found: {:?}", all_words.len());
-
-    println!("===================== Part 2 =====================");
-    let crossed_words = find_crossed_words(WORD_PART_2, &words_data);
-    println!("Crossed words found: {:?}", crossed_words.len());
-}
-
-fn read_words_data(file_path: &str) -> io::Result<Vec<String>> {
-    let path = Path::new(file_path);
-    let file = File::open(&path)?;
-    let reader = io::BufReader::new(file);
-
-    let mut words = Vec::new();
-    for line in reader.lines() {
-        let line = line?;
-        if !line.is_empty() {
-            words.push(line);
-        }
-    }
-    Ok(words)
-}
-
-fn find_all_words(word: &str, words_data: &Vec<String>) -> Vec<Direction> {
-    let mut result: Vec<Direction> = Vec::new();
-    for (row, row_data) in words_data.iter().enumerate() {
-        for (col, _) in row_data.chars().enumerate() {
-            for direction in vec![
-                Direction::Right((row, col)),
-                Direction::Left((row, col)),
-                Direction::Down((row, col)),
-                Direction::Up((row, col)),
-                Direction::DiagonalRightDown((row, col)),
-                Direction::DiagonalRightUp((row, col)),
-                Direction::DiagonalLeftDown((row, col)),
-                Direction::DiagonalLeftUp((row, col)),
-            ] {
-                if let Some(w) = get_word(word.len(), &direction, words_data) {
-                    if w == word {
-                        result.push(direction);
-                    }
-                }
+    let mut dictionary_path = None;
+    let mut list_matches = false;
+    let mut stencil_path = None;
+    let mut transforms: &[Transform] = &Transform::ROTATIONS;
+    let mut edges = Edges::Bounded;
+    let mut background = None;
+    let mut colored = false;
+    let mut pattern = None;
+    let mut ignore_case = false;
+    let mut options = args[2..].iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--words" => dictionary_path = options.next(),
+            "--matches" => list_matches = true,
+            "--wrap" => edges = Edges::Wrap,
+            "--wrap-reuse" => edges = Edges::WrapReuse,
+            "--render" => {
+                let value = options.next().map(String::as_str).unwrap_or_default();
+                background = Some(value.parse::<Background>().unwrap_or_else(|err| {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }));
             }
-        }
-    }
-    result
-}
-
-fn find_crossed_words(word: &str, words_data: &Vec<String>) -> Vec<(usize, usize)> {
-    let mut result = vec![];
-    for (row, row_data) in words_data.iter().enumerate() {
-        for (col, _) in row_data.chars().enumerate() {
-            let crossing_words = get_words_crossed_at_position(word.len(), row, col, words_data);
-            if crossing_words
-                .iter()
-                .map(|w| if w == WORD_PART_2 { 1 } else { 0 })
-                .sum::<i32>()
-                > 1
-            {
-                result.push((row, col));
+            "--color" => colored = true,
+            "--pattern" => {
+                let value = options.next().map(String::as_str).unwrap_or_default();
+                pattern = Some(value.parse::<Pattern>().unwrap_or_else(|err| {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }));
             }
-        }
-    }
-
-    result
-}
-
-fn get_word(len: usize, direction: &Direction, words_data: &Vec<String>) -> Option<String> {
-    let len = len;
-
-    match direction {
-        Direction::Right((row, col)) => {
-            if col + (len - 1) < words_data[*row].len() {
-                Some(words_data[*row][*col..*col + len].to_string())
-            } else {
-                None
-            }
-        }
-        Direction::Left((row, col)) => {
-            if *col >= (len - 1) {
-                Some(
-                    words_data[*row][col - (len - 1)..*col + 1]
-                        .chars()
-                        .rev()
-                        .collect::<String>(),
-                )
-            } else {
-                None
+            "--ignore-case" => ignore_case = true,
+            "--stencil" => stencil_path = options.next(),
+            "--transforms" => {
+                let value = options.next().map(String::as_str).unwrap_or_default();
+                transforms = Transform::set(value).unwrap_or_else(|err| {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                });
             }
-        }
-        Direction::Down((row, col)) => {
-            if row + (len - 1) < words_data.len() {
-                Some(
-                    (0..len)
-                        .map(|i| words_data[row + i].chars().nth(*col).unwrap())
-                        .collect::<String>(),
-                )
-            } else {
-                None
-            }
-        }
-        Direction::Up((row, col)) => {
-            if *row >= (len - 1) {
-                Some(
-                    (0..len)
-                        .map(|i| words_data[row - i].chars().nth(*col).unwrap())
-                        .collect::<String>(),
-                )
-            } else {
-                None
-            }
-        }
-        Direction::DiagonalRightDown((row, col)) => {
-            if row + len - 1 < words_data.len() && col + len - 1 < words_data[0].len() {
-                Some(
-                    (0..len)
-                        .map(|i| words_data[row + i].chars().nth(col + i).unwrap())
-                        .collect::<String>(),
-                )
-            } else {
-                None
-            }
-        }
-        Direction::DiagonalRightUp((row, col)) => {
-            if *row >= len - 1 && col + len - 1 < words_data[0].len() {
-                Some(
-                    (0..len)
-                        .map(|i| words_data[row - i].chars().nth(col + i).unwrap())
-                        .collect::<String>(),
-                )
-            } else {
-                None
-            }
-        }
-        Direction::DiagonalLeftDown((row, col)) => {
-            if row + len - 1 < words_data.len() && *col >= (len - 1) {
-                Some(
-                    (0..len)
-                        .map(|i| words_data[row + i].chars().nth(col - i).unwrap())
-                        .collect::<String>(),
-                )
-            } else {
-                None
-            }
-        }
-        Direction::DiagonalLeftUp((row, col)) => {
-            if *row >= (len - 1) && *col >= (len - 1) {
-                Some(
-                    (0..len)
-                        .map(|i| words_data[row - i].chars().nth(col - i).unwrap())
-                        .collect::<String>(),
-                )
-            } else {
-                None
+            _ => {
+                eprintln!("Unknown option: {}", option);
+                std::process::exit(1);
             }
         }
     }
-}
 
-fn get_words_crossed_at_position(
-    len: usize,
-    row: usize,
-    col: usize,
-    words_data: &Vec<String>,
-) -> Vec<String> {
-    let mut words = vec![];
-    if len % 2 == 0 {
-        return words;
-    }
-
-    let half_len = len / 2;
-    let max_row = words_data.len();
-    let max_col = words_data[0].len();
-
-    // Diagonal
-    if col >= half_len && col + half_len < max_col && row >= half_len && row + half_len < max_row {
-        if let Some(word) = get_word(
-            len,
-            &&Direction::DiagonalRightDown((row - half_len, col - half_len)),
-            words_data,
-        ) {
-            words.push(word);
-        }
-        if let Some(word) = get_word(
-            len,
-            &&Direction::DiagonalLeftUp((row + half_len, col + half_len)),
-            words_data,
-        ) {
-            words.push(word);
-        }
-        if let Some(word) = get_word(
-            len,
-            &&Direction::DiagonalLeftDown((row - half_len, col + half_len)),
-            words_data,
-        ) {
-            words.push(word);
-        }
-        if let Some(word) = get_word(
-            len,
-            &&Direction::DiagonalRightUp((row + half_len, col - half_len)),
-            words_data,
-        ) {
-            words.push(word);
-        }
+    // The dictionary reads every line of the grid from one edge to the other,
+    // and a line that wraps around has no edges
+    if dictionary_path.is_some() && edges != Edges::Bounded {
+        eprintln!("--words cannot be combined with --wrap or --wrap-reuse");
+        std::process::exit(1);
     }
 
-    words
-}
-#[cfg(test)]
-mod test {
-    use crate::{get_words_crossed_at_position, Direction};
-
-    #[test]
-    fn test_find_word_right() {
-        let words_data_1: Vec<String> = vec![
-            "XMAS.".to_string(),
-            ".....".to_string(),
-            ".....".to_string(),
-            ".....".to_string(),
-        ];
-        let words_data_2: Vec<String> = vec![
-            ".XMAS".to_string(),
-            ".....".to_string(),
-            ".....".to_string(),
-            ".....".to_string(),
-        ];
-        let word = "XMAS";
-        let result_1 = super::find_all_words(word, &words_data_1);
-        let result_2 = super::find_all_words(word, &words_data_2);
+    let grid = read_grid(file_path).expect("Failed to read and parse file");
 
-        assert_eq!(result_1, vec![Direction::Right((0, 0))]);
-        assert_eq!(result_2, vec![Direction::Right((0, 1))]);
+    println!("===================== Part 1 =====================");
+    let all_words = find_all_words_with(WORD_PART_1, &grid, edges);
+    println!("Words found: {:?}", all_words.len());
+    if let Some(background) = background {
+        let mut highlights = Highlights::new(&grid, edges);
+        highlights.add_words(WORD_PART_1, &all_words, colored);
+        print!("{}", highlights.render(background));
     }
 
-    #[test]
-    fn test_find_word_left() {
-        let words_data_1: Vec<String> = vec![
-            "SAMX.".to_string(),
-            ".....".to_string(),
-            ".....".to_string(),
-            ".....".to_string(),
-        ];
-        let words_data_2: Vec<String> = vec![
-            ".SAMX".to_string(),
-            ".....".to_string(),
-            ".....".to_string(),
-            ".....".to_string(),
-        ];
-        let word = "XMAS";
-        let result_1 = super::find_all_words(word, &words_data_1);
-        let result_2 = super::find_all_words(word, &words_data_2);
-
-        assert_eq!(result_1, vec![Direction::Left((0, 3))]);
-        assert_eq!(result_2, vec![Direction::Left((0, 4))]);
+    println!("===================== Part 2 =====================");
+    let crossed_words = find_crossed_words_with(WORD_PART_2, &grid, edges);
+    println!("Crossed words found: {:?}", crossed_words.len());
+    if let Some(background) = background {
+        let mut highlights = Highlights::new(&grid, edges);
+        highlights.add_crosses(WORD_PART_2, &crossed_words, colored);
+        print!("{}", highlights.render(background));
     }
 
-    #[test]
-    fn test_find_word_down() {
-        let words_data_1: Vec<String> = vec![
-            "X....".to_string(),
-            "M....".to_string(),
-            "A....".to_string(),
-            "S....".to_string(),
-            ".....".to_string(),
-        ];
-        let words_data_2: Vec<String> = vec![
-            ".....".to_string(),
-            "X....".to_string(),
-            "M....".to_string(),
-            "A....".to_string(),
-            "S....".to_string(),
-        ];
-        let word = "XMAS";
-        let result_1 = super::find_all_words(word, &words_data_1);
-        let result_2 = super::find_all_words(word, &words_data_2);
+    if let Some(dictionary_path) = dictionary_path {
+        let words = read_words_data(dictionary_path).expect("Failed to read and parse file");
+        let dictionary = Dictionary::new(&words);
+        let matches = dictionary.search(&grid);
 
-        assert_eq!(result_1, vec![Direction::Down((0, 0))]);
-        assert_eq!(result_2, vec![Direction::Down((1, 0))]);
+        println!("===================== Words ======================");
+        for (word, count) in words.iter().zip(dictionary.counts(&matches)) {
+            println!("{}: {}", word, count);
+        }
+        if list_matches {
+            for found in &matches {
+                println!(
+                    "{} {} from {:?} to {:?}",
+                    words[found.word], found.at.direction, found.at.start, found.end
+                );
+            }
+        }
     }
 
-    #[test]
-    fn test_find_word_up() {
-        let words_data_1: Vec<String> = vec![
-            "S....".to_string(),
-            "A....".to_string(),
-            "M....".to_string(),
-            "X....".to_string(),
-            ".....".to_string(),
-        ];
-        let words_data_2: Vec<String> = vec![
-            ".....".to_string(),
-            "S....".to_string(),
-            "A....".to_string(),
-            "M....".to_string(),
-            "X....".to_string(),
-        ];
-        let word = "XMAS";
-        let result_1 = super::find_all_words(word, &words_data_1);
-        let result_2 = super::find_all_words(word, &words_data_2);
-
-        assert_eq!(result_1, vec![Direction::Up((3, 0))]);
-        assert_eq!(result_2, vec![Direction::Up((4, 0))]);
+    if let Some(mut pattern) = pattern {
+        if ignore_case {
+            pattern = pattern.ignoring_case();
+        }
+        let matches = find_pattern(&pattern, &grid, edges);
+
+        println!("==================== Pattern =====================");
+        println!("Matches found: {:?}", matches.len());
+        let mut words: Vec<&str> = matches.iter().map(|found| found.letters.as_str()).collect();
+        words.sort();
+        words.dedup();
+        println!("Distinct words: {:?}", words.len());
+        if list_matches {
+            for found in &matches {
+                println!(
+                    "{} {} from {:?}",
+                    found.letters, found.at.direction, found.at.start
+                );
+            }
+        }
     }
 
-    #[test]
-    fn test_get_words_crossed_at_position() {
-        let words_data_1: Vec<String> = vec![
-            "XMAS.".to_string(),
-            ".....".to_string(),
-            ".....".to_string(),
-            ".....".to_string(),
-        ];
-        let res = get_words_crossed_at_position(3, 0, 0, &words_data_1);
-        let expected_res: Vec<String> = vec![];
+    if let Some(stencil_path) = stencil_path {
+        let stencil = Stencil::from_file(stencil_path).expect("Failed to read and parse file");
+        let matches = stencil.search_with(&grid, transforms, edges);
 
-        assert_eq!(res, expected_res);
+        println!("==================== Stencil =====================");
+        println!("Placements found: {:?}", matches.len());
     }
 }
@@ -0,0 +1,142 @@
+use std::str::FromStr;
+
+use crate::{get_word_with, Cell, Direction, Edges, Grid, Match};
+
+// A word with wildcards: `?` stands for any letter and `[AB]` for one of the
+// listed letters, e.g. `X???` or `[XS]MA[SX]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    cells: Vec<Cell>,
+    ignore_case: bool,
+}
+
+impl Pattern {
+    pub fn ignoring_case(mut self) -> Pattern {
+        self.ignore_case = true;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn matches(&self, word: &str) -> bool {
+        word.chars().count() == self.cells.len()
+            && self.cells.iter().zip(word.chars()).all(|(cell, letter)| {
+                if self.ignore_case {
+                    cell.matches_ignoring_case(letter)
+                } else {
+                    cell.matches(letter)
+                }
+            })
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut cells = vec![];
+        let mut letters = s.chars();
+        while let Some(letter) = letters.next() {
+            cells.push(Cell::parse(letter, &mut letters, "pattern", s)?);
+        }
+        Ok(Pattern {
+            cells,
+            ignore_case: false,
+        })
+    }
+}
+
+// One hit of a pattern, with the letters actually read from the grid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternMatch {
+    pub at: Match,
+    pub letters: String,
+}
+
+// Every hit of the pattern in all eight directions, in the order of
+// `find_all_words`. An empty pattern is never found.
+pub fn find_pattern(pattern: &Pattern, grid: &Grid, edges: Edges) -> Vec<PatternMatch> {
+    let mut result = vec![];
+    for row in 0..grid.rows() {
+        for col in 0..grid.cols() {
+            for direction in Direction::all() {
+                let found = direction.at((row, col));
+                if let Some(letters) = get_word_with(pattern.len(), &found, grid, edges) {
+                    if pattern.matches(&letters) {
+                        result.push(PatternMatch { at: found, letters });
+                    }
+                }
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{example_grid, find_all_words};
+
+    #[test]
+    fn test_parse_pattern() {
+        let pattern: Pattern = "X?[AB]".parse().unwrap();
+
+        assert_eq!(pattern.len(), 3);
+        assert!(pattern.matches("XMA"));
+        assert!(pattern.matches("XXB"));
+        assert!(!pattern.matches("XMS"));
+        assert!(!pattern.matches("xMA"));
+        assert!(!pattern.matches("XMAS"));
+        assert!("X[AB".parse::<Pattern>().is_err());
+        assert!("X]".parse::<Pattern>().is_err());
+        assert!("[A]B[C".parse::<Pattern>().is_err());
+    }
+
+    #[test]
+    fn test_ignore_case() {
+        let pattern = "x?[ab]".parse::<Pattern>().unwrap().ignoring_case();
+
+        assert!(pattern.matches("XmA"));
+        assert!(pattern.matches("xXb"));
+        assert!(!pattern.matches("XMS"));
+    }
+
+    #[test]
+    fn test_find_pattern() {
+        let grid = example_grid();
+
+        let exact: Vec<Match> = find_pattern(&"XMAS".parse().unwrap(), &grid, Edges::Bounded)
+            .into_iter()
+            .map(|hit| hit.at)
+            .collect();
+        assert_eq!(exact, find_all_words("XMAS", &grid));
+
+        let found = find_pattern(&"X?[AB]S".parse().unwrap(), &grid, Edges::Bounded);
+        assert_eq!(found.len(), 20);
+        assert!(found.iter().all(|found| found.letters.starts_with('X')));
+        assert_eq!(
+            found.iter().filter(|found| found.letters == "XMAS").count(),
+            18
+        );
+    }
+
+    #[test]
+    fn test_find_pattern_ignoring_case() {
+        let grid = Grid::from_rows(&["xmas", "XMAS"]).unwrap();
+
+        let found = find_pattern(&"XMAS".parse().unwrap(), &grid, Edges::Bounded);
+        assert_eq!(found.len(), 1);
+        let pattern = "XMAS".parse::<Pattern>().unwrap().ignoring_case();
+        let letters: Vec<String> = find_pattern(&pattern, &grid, Edges::Bounded)
+            .into_iter()
+            .map(|found| found.letters)
+            .collect();
+        assert_eq!(letters, vec!["xmas", "XMAS"]);
+    }
+}
@@ -0,0 +1,201 @@
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::{Direction, Edges, Grid, Match};
+
+// How letters outside every match are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Background {
+    // Replaced with `.`, like the examples of the puzzle.
+    Dots,
+    // Kept but dimmed with ANSI escapes.
+    Dim,
+}
+
+impl FromStr for Background {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dots" => Ok(Background::Dots),
+            "dim" => Ok(Background::Dim),
+            _ => Err(format!("Unknown background: {} (expected dots or dim)", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    Unmatched,
+    Matched,
+    Colored(&'static str),
+}
+
+// One color for each of `Direction::ALL`; other steps are only made bold.
+const COLORS: [&str; 8] = [
+    "\x1b[1;31m",
+    "\x1b[1;32m",
+    "\x1b[1;33m",
+    "\x1b[1;34m",
+    "\x1b[1;35m",
+    "\x1b[1;36m",
+    "\x1b[1;91m",
+    "\x1b[1;92m",
+];
+
+fn color(direction: Direction) -> &'static str {
+    Direction::all()
+        .position(|other| other == direction)
+        .map_or("\x1b[1m", |idx| COLORS[idx])
+}
+
+// Letters of the grid covered by matches. When matches overlap, the last one
+// added decides the color of a letter.
+pub struct Highlights<'a> {
+    grid: &'a Grid,
+    edges: Edges,
+    marks: Vec<Mark>,
+}
+
+impl<'a> Highlights<'a> {
+    // `edges` must be those the matches were searched with, so that wrapped
+    // matches continue on the opposite edge.
+    pub fn new(grid: &'a Grid, edges: Edges) -> Highlights<'a> {
+        Highlights {
+            grid,
+            edges,
+            marks: vec![Mark::Unmatched; grid.rows() * grid.cols()],
+        }
+    }
+
+    fn mark(&mut self, origin: (usize, usize), offset: (isize, isize), mark: Mark) {
+        if let Some((row, col)) = self.grid.offset(origin, offset, self.edges) {
+            self.marks[row * self.grid.cols() + col] = mark;
+        }
+    }
+
+    // Straight matches of `word`, as found by `find_all_words`.
+    pub fn add_words(&mut self, word: &str, matches: &[Match], colored: bool) {
+        let len = word.chars().count() as isize;
+        for found in matches {
+            let mark = if colored {
+                Mark::Colored(color(found.direction))
+            } else {
+                Mark::Matched
+            };
+            let Direction { dr, dc } = found.direction;
+            for i in 0..len {
+                self.mark(found.start, (dr * i, dc * i), mark);
+            }
+        }
+    }
+
+    // X-shaped matches of `word` around their centers, as found by
+    // `find_crossed_words`. Each arm is colored by the direction it reads in;
+    // the center belongs to both and is left uncolored.
+    pub fn add_crosses(&mut self, word: &str, centers: &[(usize, usize)], colored: bool) {
+        let half_len = word.chars().count() as isize / 2;
+        let first = word.chars().next();
+        let letter = |center, offset| {
+            self.grid
+                .offset(center, offset, self.edges)
+                .and_then(|(row, col)| self.grid.get(row, col))
+        };
+
+        let mut arms = vec![];
+        for &center in centers {
+            // Top-left to bottom-right, then bottom-left to top-right.
+            for forwards in [Direction::DIAGONAL_RIGHT_DOWN, Direction::DIAGONAL_RIGHT_UP] {
+                let from = (-half_len * forwards.dr, -half_len * forwards.dc);
+                let direction = if letter(center, from) == first {
+                    forwards
+                } else {
+                    forwards.reversed()
+                };
+                arms.push((center, forwards, direction));
+            }
+        }
+
+        for (center, Direction { dr, dc }, direction) in arms {
+            let mark = if colored {
+                Mark::Colored(color(direction))
+            } else {
+                Mark::Matched
+            };
+            for i in -half_len..=half_len {
+                self.mark(center, (dr * i, dc * i), mark);
+            }
+            self.mark(center, (0, 0), Mark::Matched);
+        }
+    }
+
+    pub fn render(&self, background: Background) -> String {
+        let mut text = String::new();
+        for row in 0..self.grid.rows() {
+            for (col, &letter) in self.grid.row(row).iter().enumerate() {
+                match (self.marks[row * self.grid.cols() + col], background) {
+                    (Mark::Unmatched, Background::Dots) => text.push('.'),
+                    (Mark::Unmatched, Background::Dim) => {
+                        write!(text, "\x1b[2m{}\x1b[0m", letter).unwrap()
+                    }
+                    (Mark::Matched, _) => text.push(letter),
+                    (Mark::Colored(code), _) => write!(text, "{}{}\x1b[0m", code, letter).unwrap(),
+                }
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{find_all_words, find_crossed_words, find_crossed_words_with};
+
+    #[test]
+    fn test_render_words() {
+        let grid = Grid::from_rows(&["ZXMAS", "SAMXZ", "ZZZZZ"]).unwrap();
+        let matches = find_all_words("XMAS", &grid);
+
+        let mut highlights = Highlights::new(&grid, Edges::Bounded);
+        highlights.add_words("XMAS", &matches, false);
+        assert_eq!(highlights.render(Background::Dots), ".XMAS\nSAMX.\n.....\n");
+        assert!(highlights
+            .render(Background::Dim)
+            .starts_with("\x1b[2mZ\x1b[0mXMAS\nSAMX\x1b[2mZ\x1b[0m\n"));
+
+        highlights.add_words("XMAS", &matches, true);
+        let colored = highlights.render(Background::Dots);
+        assert!(colored.starts_with(".\x1b[1;31mX\x1b[0m\x1b[1;31mM\x1b[0m"));
+        assert!(colored.contains("\n\x1b[1;32mS\x1b[0m"));
+    }
+
+    #[test]
+    fn test_render_crosses() {
+        let grid = Grid::from_rows(&["MZS", "ZAZ", "MZS"]).unwrap();
+        let centers = find_crossed_words("MAS", &grid);
+
+        let mut highlights = Highlights::new(&grid, Edges::Bounded);
+        highlights.add_crosses("MAS", &centers, false);
+        assert_eq!(highlights.render(Background::Dots), "M.S\n.A.\nM.S\n");
+
+        highlights.add_crosses("MAS", &centers, true);
+        assert_eq!(
+            highlights.render(Background::Dots),
+            "\x1b[1;35mM\x1b[0m.\x1b[1;36mS\x1b[0m\n\
+             .A.\n\
+             \x1b[1;36mM\x1b[0m.\x1b[1;35mS\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn test_render_wrapped_crosses() {
+        let grid = Grid::from_rows(&["AZZ", "ZSS", "ZMM"]).unwrap();
+        let centers = find_crossed_words_with("MAS", &grid, Edges::Wrap);
+
+        let mut highlights = Highlights::new(&grid, Edges::Wrap);
+        highlights.add_crosses("MAS", &centers, false);
+        assert_eq!(highlights.render(Background::Dots), "A..\n.SS\n.MM\n");
+    }
+}
@@ -0,0 +1,488 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::{Chars, FromStr};
+
+use crate::{Edges, Grid};
+
+// A single cell of a stencil.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cell {
+    // Any letter, written `?`.
+    Any,
+    Letter(char),
+    // One of several letters, written `[MS]`.
+    OneOf(Vec<char>),
+}
+
+impl Cell {
+    // Parses the cell starting with `letter`, taking the rest of a `[..]` set
+    // from `letters`. `context` and `text` only describe errors.
+    pub(crate) fn parse(
+        letter: char,
+        letters: &mut Chars,
+        context: &str,
+        text: &str,
+    ) -> Result<Cell, String> {
+        match letter {
+            '?' => Ok(Cell::Any),
+            '[' => {
+                let mut set = vec![];
+                loop {
+                    match letters.next() {
+                        Some(']') => break,
+                        Some(letter) => set.push(letter),
+                        None => return Err(format!("Unclosed [ in {}: {}", context, text)),
+                    }
+                }
+                if set.is_empty() {
+                    return Err(format!("Empty letter set in {}: {}", context, text));
+                }
+                Ok(Cell::OneOf(set))
+            }
+            ']' => Err(format!("Unmatched ] in {}: {}", context, text)),
+            _ => Ok(Cell::Letter(letter)),
+        }
+    }
+
+    pub(crate) fn matches(&self, letter: char) -> bool {
+        match self {
+            Cell::Any => true,
+            Cell::Letter(expected) => *expected == letter,
+            Cell::OneOf(letters) => letters.contains(&letter),
+        }
+    }
+
+    // Simple case folding, one letter to one letter, so that `ß` does not
+    // match `SS`.
+    pub(crate) fn matches_ignoring_case(&self, letter: char) -> bool {
+        let fold = |letter: char| letter.to_lowercase().next().unwrap_or(letter);
+        match self {
+            Cell::Any => true,
+            Cell::Letter(expected) => fold(*expected) == fold(letter),
+            Cell::OneOf(letters) => letters.iter().any(|&other| fold(other) == fold(letter)),
+        }
+    }
+}
+
+// Rotations and reflections a stencil can be placed under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transform {
+    Identity,
+    // Clockwise.
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    // Mirrored left to right.
+    FlipHorizontal,
+    // Mirrored top to bottom.
+    FlipVertical,
+    // Mirrored along the main diagonal.
+    Transpose,
+    // Mirrored along the anti-diagonal.
+    AntiTranspose,
+}
+
+impl Transform {
+    pub const NONE: [Transform; 1] = [Transform::Identity];
+    pub const ROTATIONS: [Transform; 4] = [
+        Transform::Identity,
+        Transform::Rotate90,
+        Transform::Rotate180,
+        Transform::Rotate270,
+    ];
+    pub const ALL: [Transform; 8] = [
+        Transform::Identity,
+        Transform::Rotate90,
+        Transform::Rotate180,
+        Transform::Rotate270,
+        Transform::FlipHorizontal,
+        Transform::FlipVertical,
+        Transform::Transpose,
+        Transform::AntiTranspose,
+    ];
+
+    // Named sets of transforms: `none`, `rotations` or `all`.
+    pub fn set(name: &str) -> Result<&'static [Transform], String> {
+        match name {
+            "none" => Ok(&Transform::NONE),
+            "rotations" => Ok(&Transform::ROTATIONS),
+            "all" => Ok(&Transform::ALL),
+            _ => Err(format!("Unsupported transforms: {}", name)),
+        }
+    }
+
+    fn swaps_axes(self) -> bool {
+        matches!(
+            self,
+            Transform::Rotate90
+                | Transform::Rotate270
+                | Transform::Transpose
+                | Transform::AntiTranspose
+        )
+    }
+
+    // Where the cell at (row, col) of a height x width stencil ends up.
+    fn apply(self, (row, col): (usize, usize), height: usize, width: usize) -> (usize, usize) {
+        let (last_row, last_col) = (height - 1, width - 1);
+        match self {
+            Transform::Identity => (row, col),
+            Transform::Rotate90 => (col, last_row - row),
+            Transform::Rotate180 => (last_row - row, last_col - col),
+            Transform::Rotate270 => (last_col - col, row),
+            Transform::FlipHorizontal => (row, last_col - col),
+            Transform::FlipVertical => (last_row - row, col),
+            Transform::Transpose => (col, row),
+            Transform::AntiTranspose => (last_col - col, last_row - row),
+        }
+    }
+}
+
+// A small 2D mask matched against the grid. Cells outside the mask match
+// anything, including positions off the grid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stencil {
+    height: usize,
+    width: usize,
+    // Row by row, None for cells outside the mask.
+    cells: Vec<Option<Cell>>,
+}
+
+// Placement of a stencil, by the top left corner of its transformed bounding
+// box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StencilMatch {
+    pub transform: Transform,
+    pub origin: (usize, usize),
+}
+
+impl Stencil {
+    fn from_cells(cells: &[((usize, usize), char)]) -> Stencil {
+        let height = cells
+            .iter()
+            .map(|&((row, _), _)| row + 1)
+            .max()
+            .unwrap_or(0);
+        let width = cells
+            .iter()
+            .map(|&((_, col), _)| col + 1)
+            .max()
+            .unwrap_or(0);
+        let mut stencil = Stencil {
+            height,
+            width,
+            cells: vec![None; height * width],
+        };
+        for &((row, col), letter) in cells {
+            stencil.cells[row * width + col] = Some(Cell::Letter(letter));
+        }
+        stencil
+    }
+
+    // The word on both diagonals of a square, crossing at its middle letter,
+    // as in part 2. Only odd-length words have a middle letter.
+    pub fn x_of(word: &str) -> Option<Stencil> {
+        let letters: Vec<char> = word.chars().collect();
+        let len = letters.len();
+        if len.is_multiple_of(2) {
+            return None;
+        }
+        let cells: Vec<((usize, usize), char)> = letters
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, &letter)| [((idx, idx), letter), ((len - 1 - idx, idx), letter)])
+            .collect();
+        Some(Stencil::from_cells(&cells))
+    }
+
+    // The word across and down a square, crossing at its middle letter.
+    pub fn plus_of(word: &str) -> Option<Stencil> {
+        let letters: Vec<char> = word.chars().collect();
+        let len = letters.len();
+        if len.is_multiple_of(2) {
+            return None;
+        }
+        let cells: Vec<((usize, usize), char)> = letters
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, &letter)| [((len / 2, idx), letter), ((idx, len / 2), letter)])
+            .collect();
+        Some(Stencil::from_cells(&cells))
+    }
+
+    pub fn from_file(file_path: &str) -> io::Result<Stencil> {
+        fs::read_to_string(Path::new(file_path))?
+            .parse()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    // None for cells outside the mask, including positions past its edges.
+    pub fn cell(&self, row: usize, col: usize) -> Option<&Cell> {
+        if row >= self.height || col >= self.width {
+            return None;
+        }
+        self.cells[row * self.width + col].as_ref()
+    }
+
+    pub fn transformed(&self, transform: Transform) -> Stencil {
+        let (height, width) = if transform.swaps_axes() {
+            (self.width, self.height)
+        } else {
+            (self.height, self.width)
+        };
+        let mut cells = vec![None; height * width];
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let (new_row, new_col) = transform.apply((row, col), self.height, self.width);
+                cells[new_row * width + new_col] = self.cell(row, col).cloned();
+            }
+        }
+        Stencil {
+            height,
+            width,
+            cells,
+        }
+    }
+
+    // Grid positions covered by the mask when placed at `origin`.
+    pub fn covered(&self, origin: (usize, usize)) -> Vec<(usize, usize)> {
+        (0..self.height)
+            .flat_map(|row| (0..self.width).map(move |col| (row, col)))
+            .filter(|&(row, col)| self.cell(row, col).is_some())
+            .map(|(row, col)| (origin.0 + row, origin.1 + col))
+            .collect()
+    }
+
+    fn matches_at(&self, grid: &Grid, origin: (usize, usize), edges: Edges) -> bool {
+        (0..self.height).all(|row| {
+            (0..self.width).all(|col| match self.cell(row, col) {
+                None => true,
+                Some(cell) => grid
+                    .offset(origin, (row as isize, col as isize), edges)
+                    .and_then(|(row, col)| grid.get(row, col))
+                    .is_some_and(|letter| cell.matches(letter)),
+            })
+        })
+    }
+
+    // Every placement under the given transforms. Transforms that turn the
+    // stencil into one already tried are skipped, so a symmetric stencil is
+    // not reported twice at the same place.
+    pub fn search(&self, grid: &Grid, transforms: &[Transform]) -> Vec<StencilMatch> {
+        self.search_with(grid, transforms, Edges::Bounded)
+    }
+
+    // Like `search`, with placements that may wrap around the edges. Under
+    // `Edges::Wrap` a stencil larger than the grid would cover some cell
+    // twice, so it is never placed.
+    pub fn search_with(
+        &self,
+        grid: &Grid,
+        transforms: &[Transform],
+        edges: Edges,
+    ) -> Vec<StencilMatch> {
+        let (rows, cols) = (grid.rows(), grid.cols());
+
+        let mut variants: Vec<(Transform, Stencil)> = vec![];
+        for &transform in transforms {
+            let stencil = self.transformed(transform);
+            if edges == Edges::Wrap && (stencil.height > rows || stencil.width > cols) {
+                continue;
+            }
+            if !variants.iter().any(|(_, other)| *other == stencil) {
+                variants.push((transform, stencil));
+            }
+        }
+
+        let mut matches = vec![];
+        for row in 0..rows {
+            for col in 0..cols {
+                for (transform, stencil) in &variants {
+                    if stencil.matches_at(grid, (row, col), edges) {
+                        matches.push(StencilMatch {
+                            transform: *transform,
+                            origin: (row, col),
+                        });
+                    }
+                }
+            }
+        }
+        matches
+    }
+}
+
+// One row per line: letters match themselves, `?` any letter, `[MS]` one of
+// the letters and `.` or a space is outside the mask. Lines starting with `#`
+// are comments. Shorter rows are padded with cells outside the mask, and rows
+// and columns around the mask are dropped.
+impl FromStr for Stencil {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rows: Vec<Vec<Option<Cell>>> = vec![];
+        for line in s.lines() {
+            if line.starts_with('#') {
+                continue;
+            }
+            let mut row = vec![];
+            let mut letters = line.trim_end().chars();
+            while let Some(letter) = letters.next() {
+                row.push(match letter {
+                    '.' | ' ' => None,
+                    _ => Some(Cell::parse(letter, &mut letters, "stencil row", line)?),
+                });
+            }
+            rows.push(row);
+        }
+
+        // Only the bounding box of the mask is kept, so that blank rows and
+        // columns around it do not keep it away from the edges of the grid.
+        let occupied = |row: &Vec<Option<Cell>>| row.iter().any(|cell| cell.is_some());
+        let first_row = rows
+            .iter()
+            .position(occupied)
+            .ok_or("Stencil has no cells")?;
+        let last_row = rows.iter().rposition(occupied).unwrap_or(first_row);
+        let rows = &rows[first_row..=last_row];
+        let first_col = rows
+            .iter()
+            .filter_map(|row| row.iter().position(|cell| cell.is_some()))
+            .min()
+            .unwrap_or(0);
+        let end_col = rows
+            .iter()
+            .filter_map(|row| row.iter().rposition(|cell| cell.is_some()))
+            .max()
+            .map_or(first_col, |col| col + 1);
+
+        let (height, width) = (rows.len(), end_col - first_col);
+        let cells = rows
+            .iter()
+            .flat_map(|row| (first_col..end_col).map(move |col| row.get(col).cloned().flatten()))
+            .collect();
+        Ok(Stencil {
+            height,
+            width,
+            cells,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Grid {
+        Grid::from_rows(&[
+            ".M.S......",
+            "..A..MSMS.",
+            ".M.S.MAA..",
+            "..A.ASMSM.",
+            ".M.S.M....",
+            "..........",
+            "S.S.S.S.S.",
+            ".A.A.A.A..",
+            "M.M.M.M.M.",
+            "..........",
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_stencil() {
+        let stencil: Stencil = "# X-MAS\n[MS].?\n.A\n".parse().unwrap();
+
+        assert_eq!((stencil.height(), stencil.width()), (2, 3));
+        assert_eq!(stencil.cell(0, 0), Some(&Cell::OneOf(vec!['M', 'S'])));
+        assert_eq!(stencil.cell(0, 1), None);
+        assert_eq!(stencil.cell(0, 2), Some(&Cell::Any));
+        assert_eq!(stencil.cell(1, 1), Some(&Cell::Letter('A')));
+        assert_eq!(stencil.cell(1, 2), None);
+        assert_eq!(stencil.cell(0, 3), None);
+        assert_eq!(stencil.cell(2, 0), None);
+
+        assert!("[MS".parse::<Stencil>().is_err());
+        assert!("[M]..[S".parse::<Stencil>().is_err());
+        assert!("[M]..\n[S".parse::<Stencil>().is_err());
+        assert!("[A]B[C".parse::<Stencil>().is_err());
+        assert!("[]".parse::<Stencil>().is_err());
+        assert!("..\n".parse::<Stencil>().is_err());
+    }
+
+    #[test]
+    fn test_transforms() {
+        let stencil: Stencil = "XM\nA.".parse().unwrap();
+
+        assert_eq!(
+            stencil.transformed(Transform::Rotate90),
+            "AX\n.M".parse().unwrap()
+        );
+        assert_eq!(
+            stencil.transformed(Transform::Rotate270),
+            "M.\nXA".parse().unwrap()
+        );
+        assert_eq!(
+            stencil.transformed(Transform::AntiTranspose),
+            ".M\nAX".parse().unwrap()
+        );
+        for transform in Transform::ALL {
+            assert_eq!(stencil.transformed(transform).covered((0, 0)).len(), 3);
+        }
+    }
+
+    #[test]
+    fn test_x_mas_stencil() {
+        let stencil = Stencil::x_of("MAS").unwrap();
+        assert_eq!(stencil, "M.S\n.A.\nM.S".parse().unwrap());
+
+        let matches = stencil.search(&example(), &Transform::ROTATIONS);
+        assert_eq!(matches.len(), 9);
+        // Reflections only add placements already found by rotations
+        assert_eq!(stencil.search(&example(), &Transform::ALL).len(), 9);
+        assert!(Stencil::x_of("XMAS").is_none());
+    }
+
+    #[test]
+    fn test_blank_rows_and_columns_are_dropped() {
+        let stencil: Stencil = "\n. M.S.\n  .A.\n .M.S\n\n..".parse().unwrap();
+        assert_eq!(stencil, "M.S\n.A.\nM.S".parse().unwrap());
+        assert_eq!(stencil.search(&example(), &Transform::ROTATIONS).len(), 9);
+
+        let grid = Grid::from_rows(&["AZ", "ZA"]).unwrap();
+        let a: Stencil = ".A".parse().unwrap();
+        assert_eq!((a.height(), a.width()), (1, 1));
+        assert_eq!(a.search(&grid, &Transform::NONE).len(), 2);
+    }
+
+    #[test]
+    fn test_plus_and_l_stencils() {
+        let grid = Grid::from_rows(&[".M..", "MAS.", ".S..", ".MX."]).unwrap();
+
+        let plus = Stencil::plus_of("MAS").unwrap();
+        assert_eq!(
+            plus.search(&grid, &Transform::NONE),
+            vec![StencilMatch {
+                transform: Transform::Identity,
+                origin: (0, 0),
+            }]
+        );
+
+        let l_shape: Stencil = "S\n?\nXM".parse().unwrap();
+        let matches = l_shape.search(&grid, &Transform::ALL);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(
+            l_shape
+                .transformed(matches[0].transform)
+                .covered(matches[0].origin),
+            vec![(1, 2), (2, 2), (3, 1), (3, 2)]
+        );
+    }
+}